@@ -1,17 +1,76 @@
-use std::fs;
+#![allow(clippy::upper_case_acronyms)]
+
+use std::path::Path;
 
 mod postfix {   
     pub mod enums;
     pub mod lexer;
+    #[cfg(test)]
     pub mod tests;
     pub mod errors;
+    pub mod config;
+    pub mod prelude;
+    pub mod modules;
+    pub mod io;
+    pub mod natives;
+    pub mod effects;
+    pub mod types;
+    pub mod energy;
+    pub mod reports;
+    pub mod optimizer;
+    pub mod specializer;
+    pub mod symbolic;
+    pub mod equivalence;
+    pub mod lints;
+    pub mod fixes;
+    pub mod superoptimizer;
     pub mod programs;
 }
 
 use postfix :: {
     programs :: {
         compile_and_run,
-    }
+        compile_and_run_with_config,
+        compile_file,
+        compile_file_and_run,
+        parse_tokens_to_program,
+    },
+    lexer :: parse_code_to_tokens,
+    effects :: {
+        command_effect,
+        sequence_effect,
+    },
+    types :: infer_types,
+    reports :: analysis_report,
+    optimizer :: {
+        PostfixOptimizer,
+        PostfixRewrite,
+    },
+    specializer :: specialize,
+    symbolic :: execute_symbolically,
+    lints :: {
+        lint_code,
+        lint_file,
+    },
+    fixes :: {
+        find_dead_code,
+        fix_dead_code,
+    },
+    superoptimizer :: PostfixSuperoptimizer,
+    equivalence :: {
+        check_equivalence,
+        EquivalenceBounds,
+    },
+    modules :: FileSystemLoader,
+    io :: StandardIo,
+    natives :: NativeRegistry,
+    config :: {
+        PostfixConfig,
+        PostfixExtension,
+        PostfixConversion,
+        PostfixOverflow,
+        PostfixTyping,
+    },
 };
 
 enum CodeType<'a> {
@@ -21,6 +80,99 @@ enum CodeType<'a> {
 
 fn main () {
     use CodeType::*;
+
+    // `postfix analyze <file>` reports on a program instead of running the examples, `postfix lint <file>`
+    // points out suspicious code in it, `postfix fix <file>` rewrites it without its dead code,
+    // `postfix superopt <file> [length]` looks for the shortest program doing the same, and
+    // `postfix equiv <file> <file> [low high [samples [seed]]]` compares two of them
+    match std::env::args().collect::<Vec<_>>().as_slice() {
+        [_, command, path] if command == "analyze" => {
+            let config = PostfixConfig::core().without_trace();
+            match compile_file(Path::new(path), &config, &FileSystemLoader) {
+                Ok(program) => print! ("{}", analysis_report(&program, &config)),
+                Err(error) => println! ("{:?}", error),
+            };
+            return;
+        },
+        [_, command, path] if command == "lint" => {
+            match lint_file(Path::new(path), &PostfixConfig::core(), &FileSystemLoader) {
+                Ok(lints) => lints.iter().for_each(|lint| println! ("{}", lint)),
+                Err(error) => println! ("{:?}", error),
+            };
+            return;
+        },
+        [_, command, path] if command == "fix" => {
+            let config = PostfixConfig::core().without_trace();
+            match compile_file(Path::new(path), &config, &FileSystemLoader).and_then(|program| fix_dead_code(&program, &EquivalenceBounds::default(), &config)) {
+                Ok(Some(fix)) => {
+                    fix.removed.iter().for_each(|removal| println! ("{}", removal));
+                    // the program comes from the compiled one, with definitions, imports and the prelude inlined
+                    println! ("; derived from {} as compiled", path);
+                    println! ("{}", fix.program);
+                    println! ("{:?}", fix.verification);
+                },
+                Ok(None) => println! ("no dead code"),
+                Err(error) => println! ("{:?}", error),
+            };
+            return;
+        },
+        [_, command, path, length @ ..] if command == "superopt" && length.len() <= 1 => {
+            let config = PostfixConfig::core().without_trace();
+            let superoptimizer = match length.first().map(|length| length.parse::<usize>()) {
+                None => PostfixSuperoptimizer::default(),
+                Some(Ok(length)) => PostfixSuperoptimizer::default().with_max_length(length),
+                Some(Err(_)) => {
+                    println! ("usage: postfix superopt <file> [length]");
+                    return;
+                },
+            };
+            match compile_file(Path::new(path), &config, &FileSystemLoader).and_then(|program| superoptimizer.superoptimize(&program, &config)) {
+                Ok(Some(program)) => println! ("{}", program),
+                Ok(None) => println! ("nothing shorter found"),
+                Err(error) => println! ("{:?}", error),
+            };
+            return;
+        },
+        [_, command, left, right, bounds @ ..] if command == "equiv" => {
+            let config = PostfixConfig::core().without_trace();
+            let parsed = match bounds {
+                [] => Ok(EquivalenceBounds::default()),
+                [low, high, rest @ ..] if rest.len() <= 2 => {
+                    let samples = rest.first().map(|samples| samples.parse::<usize>()).transpose();
+                    let seed = rest.get(1).map(|seed| seed.parse::<u64>()).transpose();
+                    match (low.parse::<i32>(), high.parse::<i32>(), samples, seed) {
+                        (Ok(low), Ok(high), Ok(samples), Ok(seed)) => {
+                            let bounds = EquivalenceBounds::new(low, high);
+                            let bounds = match samples {
+                                Some(samples) => bounds.with_samples(samples),
+                                None => bounds,
+                            };
+                            Ok(match seed {
+                                Some(seed) => bounds.with_seed(seed),
+                                None => bounds,
+                            })
+                        },
+                        _ => Err(()),
+                    }
+                },
+                _ => Err(()),
+            };
+            let bounds = match parsed {
+                Ok(bounds) => bounds,
+                Err(()) => {
+                    println! ("usage: postfix equiv <file> <file> [low high [samples [seed]]]");
+                    return;
+                },
+            };
+            match compile_file(Path::new(left), &config, &FileSystemLoader).and_then(|left| compile_file(Path::new(right), &config, &FileSystemLoader).map(|right| (left, right))) {
+                Ok((left, right)) => println! ("{:?}", check_equivalence(&left, &right, &bounds, &config)),
+                Err(error) => println! ("{:?}", error),
+            };
+            return;
+        },
+        _ => (),
+    };
+
     let testcases = vec![
         (FILE("code/postfix/ex5.postfix"), vec![]),
         (FILE("code/postfix/ex6.postfix"), vec![]),
@@ -51,6 +203,7 @@ fn main () {
         (CODE("(postfix 1 (2 mul) 1 nget)"), vec![3]),
         (CODE("(postfix 1 1 nget mul)"), vec![5]),
         (CODE("(postfix 4 4 nget 5 nget mul mul swap 4 nget mul add add)"), vec![3, 4, 5, 2]),
+        (CODE("(postfix (a b c x) a x x mul mul b x mul add c add)"), vec![3, 4, 5, 2]),
         (CODE("(postfix 1 (2 mul) exec)"), vec![7]),
         (CODE("(postfix 0 (0 swap sub) 7 swap exec)"), vec![]),
         (CODE("(postfix 0 (2 mul))"), vec![]),
@@ -86,16 +239,214 @@ fn main () {
         (CODE("(postfix 2 (1 nget) (0) sel exec)"), vec![123, 3]),
         (CODE("(postfix 2 (1 nget) (0) sel exec)"), vec![123, 0]),
         (CODE("(postfix 2 (1 nget) (0) sel exec)"), vec![0, 0]),
+
+        // named definitions
+        (CODE("(postfix 1 (def abs (1 nget 0 lt (0 swap sub) () sel exec)) abs)"), vec![-7]),
+        (CODE("(postfix 2 (def not (0 1 sel)) (def and ((1 0 sel) (0) sel exec)) and not)"), vec![6, 0]),
     ];
 
-    testcases.iter().map(|(testcase, arguments)|{
-        let code = match testcase {
-            FILE(filename) => fs::read_to_string(filename),
-            CODE(code) => Ok(code.to_string()),
-        };
-        match code {
-            Ok(code) => println! ("{:?}", compile_and_run(&code, arguments)),
-            Err(_) => panic! ("read file failure!"),
+    let core_config = PostfixConfig::core();
+    let loop_config = PostfixConfig::core().with_extension(PostfixExtension::LOOP);
+    let memory_config = PostfixConfig::core().with_extension(PostfixExtension::MEMORY).with_memory_size(4);
+    let core_without_prelude = PostfixConfig::core().without_prelude();
+    let io_config = PostfixConfig::core().with_extension(PostfixExtension::IO);
+    let math_config = PostfixConfig::core().with_extension(PostfixExtension::MATH);
+    let wrapping_config = PostfixConfig::core().with_extension(PostfixExtension::MATH).with_overflow(PostfixOverflow::WRAPPING);
+    let rational_config = PostfixConfig::core().with_extension(PostfixExtension::RATIONAL);
+    let list_config = PostfixConfig::core().with_extension(PostfixExtension::LIST);
+    let checked_config = PostfixConfig::core().with_stack_check();
+    let typed_config = PostfixConfig::core().with_type_check();
+    let strict_config = PostfixConfig::core().with_typing(PostfixTyping::STRICT);
+    let string_config = PostfixConfig::core().with_extension(PostfixExtension::IO).with_extension(PostfixExtension::STRING);
+    let native_config = match NativeRegistry::default().register("gcd", 2, |arguments| gcd(arguments[0], arguments[1]).map(|divisor| vec![divisor])) {
+        Ok(natives) => PostfixConfig::core().with_natives(natives),
+        Err(error) => panic! ("{:?}", error),
+    };
+    let exception_config = PostfixConfig::core().with_extension(PostfixExtension::EXCEPTION);
+    let library_config = PostfixConfig::core().with_search_path("code/postfix/lib");
+    let extension_testcases = [
+        // definitions imported from code/postfix/lib
+        (FILE("code/postfix/ex10.postfix"), vec![3], &library_config),
+        (FILE("code/postfix/ex10.postfix"), vec![3], &core_config),
+
+        // routines of the prelude, and the same program once it is disabled
+        (CODE("(postfix 2 max 4 ge)"), vec![3, 7], &core_config),
+        (CODE("(postfix 1 abs)"), vec![-7], &core_config),
+        (CODE("(postfix 1 abs)"), vec![-7], &core_without_prelude),
+
+        // summation of any number of arguments
+        (CODE("(postfix * 0 swap (pop add) repeat)"), vec![1, 2, 3, 4, 5], &loop_config),
+        (CODE("(postfix 1+ 1 sub (pop add) repeat)"), vec![], &loop_config),
+
+        // summation 1 + 2 + ... + n
+        (CODE("(postfix 1 0 swap (add) repeat)"), vec![10], &loop_config),
+        // factorial
+        (CODE("(postfix 1 1 swap (mul) repeat)"), vec![5], &loop_config),
+        // sum of squares
+        (CODE("(postfix 1 0 swap (1 nget mul add) repeat)"), vec![3], &loop_config),
+
+        // accumulate through a memory cell from within nested execs
+        (CODE("(postfix 2 0 put ((0 get add 0 put) exec) exec 0 get)"), vec![3, 4], &memory_config),
+        (CODE("(postfix 0 1 get)"), vec![], &memory_config),
+        (CODE("(postfix 0 7 4 put)"), vec![], &memory_config),
+
+        // command registered by the host
+        (CODE("(postfix 2 gcd)"), vec![12, 18], &native_config),
+        (CODE("(postfix 1 gcd)"), vec![12], &native_config),
+        (CODE("(postfix 2 gcd)"), vec![i32::MIN, -1], &native_config),
+        (CODE("(postfix 2 gcd)"), vec![i32::MIN, 0], &native_config),
+
+        // failures caught by a handler, which receives their code
+        (CODE("(postfix 2 (div) (pop 0) try)"), vec![0, 5], &exception_config),
+        (CODE("(postfix 1 (1 nget 0 lt (7 throw) () sel exec) (pop 0) try)"), vec![-3], &exception_config),
+
+        // extended arithmetic, failing or wrapping around on overflow, and its bitwise operators
+        (CODE("(postfix 2 2 pow swap 2 pow add 1 shl)"), vec![3, 4], &math_config),
+        (CODE("(postfix 1 31 pow)"), vec![2], &math_config),
+        (CODE("(postfix 1 31 pow)"), vec![2], &wrapping_config),
+        (CODE("(postfix 0 12 10 band 12 10 bxor bor)"), vec![], &math_config),
+
+        // price with 7.5% tax, exact until the result has to be an integer
+        (CODE("(postfix 1 1.075 mul)"), vec![200], &rational_config),
+        (CODE("(postfix 1 1.075 mul)"), vec![199], &rational_config),
+
+        // sum of squares over a list, and an index out of its range
+        (CODE("(postfix 0 [1 2 3 4] (1 nget mul) map 0 (add) fold)"), vec![], &list_config),
+        (CODE("(postfix 1 [3 1 4] swap at)"), vec![4], &list_config),
+
+        // underflow reported before running, and the same program run unchecked
+        (CODE("(postfix 1 4 mul add)"), vec![3], &checked_config),
+        (CODE("(postfix 1 4 mul add)"), vec![3], &core_config),
+        (CODE("(postfix 1 4 lt (add) (mul) sel exec)"), vec![3], &checked_config),
+
+        // kind mismatches reported before running, with where they are written
+        (CODE("(postfix 0 3 exec)"), vec![], &typed_config),
+        (CODE("(postfix 1 (2 (3) mul) exec)"), vec![3], &typed_config),
+        (CODE("(postfix 1 1 nget 0 lt (neg) () sel exec)"), vec![-3], &typed_config),
+
+        // comparisons yield booleans, which are no numbers
+        (CODE("(postfix 1 4 lt 10 add)"), vec![3], &core_config),
+        (CODE("(postfix 1 4 lt 10 add)"), vec![3], &strict_config),
+        (CODE("(postfix 1 0 gt true and 1 0 sel)"), vec![3], &strict_config),
+
+        // labeled output through the host
+        (CODE("(postfix 2 add (115 emit 117 emit 109 emit 61 emit 1 nget print) exec)"), vec![3, 4], &io_config),
+        (CODE("(postfix 2 add \"sum: \" 2 nget str concat print)"), vec![3, 4], &string_config),
+        (CODE("(postfix 0 \"12\" int \"3\" int add)"), vec![], &string_config),
+    ];
+
+    testcases.iter().for_each(|(testcase, arguments)|{
+        println! ("{:?}", match testcase {
+            FILE(filename) => compile_file_and_run(Path::new(filename), arguments, &core_config, &FileSystemLoader, &mut StandardIo),
+            CODE(code) => compile_and_run(code, arguments),
+        })
+    });
+
+    extension_testcases.iter().for_each(|(testcase, arguments, config)|{
+        println! ("{:?}", match testcase {
+            FILE(filename) => compile_file_and_run(Path::new(filename), arguments, config, &FileSystemLoader, &mut StandardIo),
+            CODE(code) => compile_and_run_with_config(code, arguments, config),
+        })
+    });
+
+    // stack effects inferred for each command and for the whole body of a program
+    match parse_code_to_tokens("(postfix 2 (swap pop) exec (1 nget mul) exec 3 sel)").and_then(|tokens| parse_tokens_to_program(tokens, &core_config)) {
+        Ok(program) => {
+            program.commands.iter().for_each(|command| println! ("{:?} {:?}", command, command_effect(command)));
+            println! ("{:?}", sequence_effect(&program.commands));
+        },
+        Err(error) => println! ("{:?}", error),
+    };
+
+    // types left on the stack by a program, sequences with their effect
+    match parse_code_to_tokens("(postfix 1 2 (1 nget mul) 3 nget 0 lt (add) (mul) sel)").and_then(|tokens| parse_tokens_to_program(tokens, &core_config)) {
+        Ok(program) => println! ("{:?}", infer_types(&program, &core_config)),
+        Err(error) => println! ("{:?}", error),
+    };
+
+    // a program before and after optimization, then with constant folding left out
+    match parse_code_to_tokens("(postfix 1 3 4 add 5 mul swap swap 1 nget pop (2 mul) exec 1 (add) (sub) sel exec)").and_then(|tokens| parse_tokens_to_program(tokens, &core_config)) {
+        Ok(program) => {
+            println! ("{:?}", program.commands);
+            println! ("{:?}", PostfixOptimizer::all().optimize(&program, &core_config).commands);
+            println! ("{:?}", PostfixOptimizer::all().without(PostfixRewrite::FOLDCONSTANTS).optimize(&program, &core_config).commands);
+            println! ("{:?}", PostfixOptimizer::none().with(PostfixRewrite::INLINEEXEC).optimize_commands(&program.commands, &core_config));
+        },
+        Err(error) => println! ("{:?}", error),
+    };
+
+    // a polynomial specialized for its coefficients, and an absolute value for its argument
+    match parse_code_to_tokens("(postfix (a b c x) a x x mul mul b x mul add c add)").and_then(|tokens| parse_tokens_to_program(tokens, &core_config)) {
+        Ok(program) => println! ("{:?}", specialize(&program, &[Some(3), Some(4), Some(5), None], &core_config).map(|residual| residual.commands)),
+        Err(error) => println! ("{:?}", error),
+    };
+    match parse_code_to_tokens("(postfix 2 1 nget 0 lt (neg) () sel exec add)").and_then(|tokens| parse_tokens_to_program(tokens, &core_config)) {
+        Ok(program) => println! ("{:?}", specialize(&program, &[Some(-3), None], &core_config).map(|residual| residual.commands)),
+        Err(error) => println! ("{:?}", error),
+    };
+
+    // the short-circuit and as a piecewise formula, and the arguments that make a division fail
+    ["(postfix 2 (1 nget) (0) sel exec)", "(postfix 2 1 nget 0 lt (neg) () sel exec div)"].iter().for_each(|code| {
+        match parse_code_to_tokens(code).and_then(|tokens| parse_tokens_to_program(tokens, &core_config)).and_then(|program| execute_symbolically(&program).map(|paths| (program, paths))) {
+            Ok((program, paths)) => paths.iter().for_each(|path| println! ("{} {:?}", path, path.witness(program.paramsize as usize, &[0, 1, -1, 2, -2, i32::MIN]))),
+            Err(error) => println! ("{:?}", error),
+        }
+    });
+
+    // a rewrite that keeps the result over a wide sampled range, and one that gets a division backwards
+    [("(postfix 2 add)", "(postfix 2 swap add)"), ("(postfix 2 div)", "(postfix 2 swap div)")].iter().for_each(|(left, right)| {
+        let bounds = EquivalenceBounds::new(-1000, 1000).with_exhaustive_limit(0).with_samples(200);
+        match parse_code_to_tokens(left).and_then(|tokens| parse_tokens_to_program(tokens, &core_config))
+            .and_then(|left| parse_code_to_tokens(right).and_then(|tokens| parse_tokens_to_program(tokens, &core_config)).map(|right| (left, right))) {
+            Ok((left, right)) => println! ("{:?}", check_equivalence(&left, &right, &bounds, &core_config)),
+            Err(error) => println! ("{:?}", error),
         }
-    }).collect()
-}
\ No newline at end of file
+    });
+
+    // lints of a program with a dead branch, a dropped argument and a swap undone, the swap allowed on its line
+    match lint_code("(postfix 2 ; allow(no-op-pair)\n swap swap pop 1 nget 0 lt 1 add 1 (2) (3) sel exec)", &core_config) {
+        Ok(lints) => lints.iter().for_each(|lint| println! ("{}", lint)),
+        Err(error) => println! ("{:?}", error),
+    };
+
+    // a comparison computed for nothing, an argument dropped and one left under the result, taken out
+    match parse_code_to_tokens("(postfix 3 1 nget 0 lt pop pop 1 nget mul)").and_then(|tokens| parse_tokens_to_program(tokens, &core_config)) {
+        Ok(program) => {
+            println! ("{:?}", find_dead_code(&program, &core_config));
+            match fix_dead_code(&program, &EquivalenceBounds::default(), &core_config) {
+                Ok(Some(fix)) => println! ("{} {:?}", fix.program, fix.verification),
+                Ok(None) => println! ("no dead code"),
+                Err(error) => println! ("{:?}", error),
+            }
+        },
+        Err(error) => println! ("{:?}", error),
+    };
+
+    // the shortest programs found for a doubling written the long way, and for a sum given by examples
+    let superoptimizer = PostfixSuperoptimizer::default().with_literals(vec![0, 1, 2]).with_max_body_length(1).with_test_values(vec![0, 1, -1, 5]).with_max_examples(1_000).with_bounds(EquivalenceBounds::new(-3, 3));
+    match parse_code_to_tokens("(postfix 1 1 nget 1 nget add 0 add)").and_then(|tokens| parse_tokens_to_program(tokens, &core_config)) {
+        Ok(program) => match superoptimizer.superoptimize(&program, &core_config) {
+            Ok(found) => println! ("{:?}", found.map(|program| program.to_string())),
+            Err(error) => println! ("{:?}", error),
+        },
+        Err(error) => println! ("{:?}", error),
+    };
+    println! ("{:?}", superoptimizer.with_max_length(3).synthesize(2, &[(vec![3, 4], 7), (vec![1, -2], -1)], &core_config).map(|program| program.to_string()));
+
+    // the same inexact result under every conversion policy
+    [PostfixConversion::EXACT, PostfixConversion::TRUNCATE, PostfixConversion::FLOOR, PostfixConversion::CEIL, PostfixConversion::ROUND].iter().for_each(|conversion| {
+        let config = PostfixConfig::core().with_extension(PostfixExtension::RATIONAL).with_conversion(*conversion);
+        println! ("{:?}", compile_and_run_with_config("(postfix 1 0 1.075 sub mul)", &vec![199], &config))
+    });
+}
+
+// taken on magnitudes, so that the least integer only fails when the result itself does not fit
+fn gcd(a: i32, b: i32) -> Result<i32, String> {
+    fn magnitude(a: u32, b: u32) -> u32 {
+        match b {
+            0 => a,
+            b => magnitude(b, a % b),
+        }
+    }
+    i32::try_from(magnitude(a.unsigned_abs(), b.unsigned_abs())).map_err(|_| format!("gcd of {} and {} is too large", a, b))
+}
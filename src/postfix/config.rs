@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use num::{
+    rational::Rational64,
+    Zero,
+};
+
+use super::{
+    enums::PostfixFunction,
+    natives::NativeRegistry,
+    prelude::PRELUDE_VERSION,
+};
+
+
+// language extensions on top of core PostFix; a function belonging to extensions
+// is only accepted by the parser when one of them is enabled
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum PostfixExtension {
+    LOOP,
+    MEMORY,
+    IO,
+    EXCEPTION,
+    STRING,
+    MATH,
+    RATIONAL,
+    LIST,
+}
+
+// how truth values are represented: as the integers 1 and 0 like in core PostFix,
+// or as booleans that can be neither computed with nor replaced by integers
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum PostfixTyping {
+    LOOSE,
+    STRICT,
+}
+
+// what integer operations do when their result does not fit: fail, or wrap around in two's complement.
+// Either way this applies to core add, sub, mul and div as well, which used to panic on overflow; so did
+// rem by zero, which now fails with DivideByZero like div
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum PostfixOverflow {
+    CHECKED,
+    WRAPPING,
+}
+
+// how a rational left on top of the stack becomes the integer result of the program
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum PostfixConversion {
+    // only whole numbers are accepted
+    EXACT,
+    TRUNCATE,
+    FLOOR,
+    CEIL,
+    // to the nearest integer, halves away from zero
+    ROUND,
+}
+
+impl PostfixConversion {
+    pub fn convert(&self, value: Rational64) -> Option<i32> {
+        use PostfixConversion::*;
+        let whole = match self {
+            EXACT => match value.fract().is_zero() {
+                true => value,
+                false => return None,
+            },
+            TRUNCATE => value.trunc(),
+            FLOOR => value.floor(),
+            CEIL => value.ceil(),
+            ROUND => value.round(),
+        };
+        i32::try_from(whole.to_integer()).ok()
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct PostfixConfig {
+    pub extensions: Vec<PostfixExtension>,
+    // number of cells available to get/put when the memory extension is enabled
+    pub memory_size: usize,
+    // version of the prelude made available to programs, None to disable it
+    pub prelude: Option<u32>,
+    // directories searched by `(import name)` after the directory of the importing file
+    pub search_path: Vec<PathBuf>,
+    // commands provided by the host, resolved by name like definitions
+    pub natives: NativeRegistry,
+    pub typing: PostfixTyping,
+    pub overflow: PostfixOverflow,
+    pub conversion: PostfixConversion,
+    // reject programs that must underflow before running them
+    pub check_stack: bool,
+    // reject programs that must give a command a value of the wrong kind before running them
+    pub check_types: bool,
+    // print the commands and the stack before every step of a run
+    pub trace: bool,
+}
+
+impl PostfixConfig {
+
+    pub fn core() -> Self {
+        Self { extensions: Vec::new(), memory_size: 16, prelude: Some(PRELUDE_VERSION), search_path: Vec::new(), natives: NativeRegistry::default(), typing: PostfixTyping::LOOSE, overflow: PostfixOverflow::CHECKED, conversion: PostfixConversion::EXACT, check_stack: false, check_types: false, trace: true }
+    }
+
+    pub fn with_extension(mut self, extension: PostfixExtension) -> Self {
+        match self.extensions.contains(&extension) {
+            true => self,
+            false => { self.extensions.push(extension); self },
+        }
+    }
+
+    pub fn with_memory_size(mut self, memory_size: usize) -> Self {
+        self.memory_size = memory_size;
+        self
+    }
+
+    pub fn without_prelude(mut self) -> Self {
+        self.prelude = None;
+        self
+    }
+
+    pub fn with_search_path(mut self, directory: &str) -> Self {
+        self.search_path.push(PathBuf::from(directory));
+        self
+    }
+
+    pub fn with_natives(mut self, natives: NativeRegistry) -> Self {
+        self.natives = natives;
+        self
+    }
+
+    pub fn with_typing(mut self, typing: PostfixTyping) -> Self {
+        self.typing = typing;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: PostfixOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn with_conversion(mut self, conversion: PostfixConversion) -> Self {
+        self.conversion = conversion;
+        self
+    }
+
+    pub fn with_stack_check(mut self) -> Self {
+        self.check_stack = true;
+        self
+    }
+
+    pub fn with_type_check(mut self) -> Self {
+        self.check_types = true;
+        self
+    }
+
+    pub fn without_trace(mut self) -> Self {
+        self.trace = false;
+        self
+    }
+
+    pub fn supports(&self, function: &PostfixFunction) -> bool {
+        match function.extensions() {
+            [] => true,
+            extensions => extensions.iter().any(|extension| self.extensions.contains(extension)),
+        }
+    }
+}
+
+impl Default for PostfixConfig {
+    fn default() -> Self {
+        Self::core()
+    }
+}
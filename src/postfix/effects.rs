@@ -0,0 +1,188 @@
+use super::{
+    enums::{
+        PostfixCommand,
+        PostfixFunction,
+        PostfixProgram,
+    },
+    errors::PostfixError,
+};
+
+
+// values a sequence takes from the stack it runs on, and values it leaves in their place
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub struct StackEffect {
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+// what is statically known of a value on the stack
+#[derive(Debug,Clone,PartialEq)]
+enum AbstractValue {
+    VALUE,
+    INTEGER(i32),
+    SEQUENCE(Vec<PostfixCommand>),
+    // one of two sequences, as chosen by a sel
+    EITHER(Vec<PostfixCommand>, Vec<PostfixCommand>),
+}
+
+impl AbstractValue {
+    // what is known of a value that is one or the other
+    fn join(self, other: AbstractValue) -> AbstractValue {
+        use AbstractValue::*;
+        match (self, other) {
+            (left, right) if left == right => left,
+            (SEQUENCE(left), SEQUENCE(right)) => EITHER(left, right),
+            (_, _) => VALUE,
+        }
+    }
+}
+
+// the stack as seen by the analysis: the values pushed since the start, above `unknown` values of which
+// nothing is known, themselves above `borrowed` values taken from below the start; when the stack is closed
+// nothing lies below the start. Values of which nothing is known are only counted, as an nget may reach far down
+#[derive(Debug,Clone,PartialEq)]
+struct AbstractStack {
+    values: Vec<AbstractValue>,
+    unknown: usize,
+    borrowed: usize,
+    closed: bool,
+}
+
+impl AbstractStack {
+
+    fn height(&self) -> usize {
+        self.unknown + self.values.len()
+    }
+
+    // the top `count` values, deepest first
+    fn pop(&mut self, function: PostfixFunction, count: usize) -> Result<Vec<AbstractValue>, PostfixError> {
+        let known = self.values.len().min(count);
+        let unknown = self.unknown.min(count - known);
+        let missing = count - known - unknown;
+        match (missing, self.closed) {
+            (1.., true) => Err(PostfixError::StackUnderflow { function, expected_number_of_arguments: count, available: self.height() }),
+            (_, _) => {
+                self.unknown -= unknown;
+                self.borrowed += missing;
+                Ok(std::iter::repeat_n(AbstractValue::VALUE, missing + unknown).chain(self.values.split_off(self.values.len() - known)).collect())
+            },
+        }
+    }
+
+    // the value `depth` down from the top, left in place; values borrowed to reach it are only counted
+    fn copy(&mut self, depth: usize) -> AbstractValue {
+        match self.values.len().checked_sub(depth) {
+            Some(position) => self.values[position].to_owned(),
+            None => {
+                let missing = (depth - self.values.len()).saturating_sub(self.unknown);
+                self.borrowed += missing;
+                self.unknown += missing;
+                AbstractValue::VALUE
+            },
+        }
+    }
+
+    fn push(mut self, value: AbstractValue) -> Self {
+        self.values.push(value);
+        self
+    }
+
+    // the stack after either of two paths, None when they disagree on its shape
+    fn join(self, other: AbstractStack) -> Option<AbstractStack> {
+        match (self.height() == other.height(), self.borrowed == other.borrowed) {
+            (true, true) => {
+                // the values known on one side only are taken for unknown
+                let unknown = self.unknown.min(other.unknown);
+                let known = |stack: AbstractStack| std::iter::repeat_n(AbstractValue::VALUE, stack.unknown - unknown).chain(stack.values);
+                Some(AbstractStack {
+                    unknown,
+                    borrowed: self.borrowed,
+                    closed: self.closed,
+                    values: known(self).zip(known(other)).map(|(left, right)| left.join(right)).collect(),
+                })
+            },
+            (_, _) => None,
+        }
+    }
+}
+
+// the stack after running `commands`: Ok(None) once it depends on values only known at run time,
+// an error when every way of running them underflows
+fn analyze(commands: &[PostfixCommand], stack: AbstractStack) -> Result<Option<AbstractStack>, PostfixError> {
+    commands.iter().try_fold(Some(stack), |stack, command| match stack {
+        Some(stack) => step(command, stack),
+        None => Ok(None),
+    })
+}
+
+fn step(command: &PostfixCommand, mut stack: AbstractStack) -> Result<Option<AbstractStack>, PostfixError> {
+    use PostfixCommand::*;
+    use PostfixFunction::*;
+    match command {
+        EXECUTE(commands) => Ok(Some(stack.push(AbstractValue::SEQUENCE(commands.to_owned())))),
+        SPECIAL(EXEC) => match stack.pop(EXEC, 1)?.pop() {
+            Some(AbstractValue::SEQUENCE(commands)) => analyze(&commands, stack),
+            // a path that must underflow leaves the other one to decide
+            Some(AbstractValue::EITHER(left, right)) => match (analyze(&left, stack.clone()), analyze(&right, stack)) {
+                (Err(error), Err(_)) => Err(error),
+                (Err(_), Ok(stack)) | (Ok(stack), Err(_)) => Ok(stack),
+                (Ok(Some(left)), Ok(Some(right))) => Ok(left.join(right)),
+                (Ok(_), Ok(_)) => Ok(None),
+            },
+            Some(AbstractValue::VALUE | AbstractValue::INTEGER(_)) | None => Ok(None),
+        },
+        SPECIAL(SWAP) => {
+            let values = stack.pop(SWAP, 2)?;
+            Ok(Some(values.into_iter().rev().fold(stack, |stack, value| stack.push(value))))
+        },
+        // a copy from a known depth needs that many values below the index
+        SPECIAL(NGET) => match stack.pop(NGET, 1)?.pop() {
+            // out of range is left to the run time, where it can be caught
+            Some(AbstractValue::INTEGER(index)) if stack.closed && stack.height() < index as usize => Ok(None),
+            Some(AbstractValue::INTEGER(index)) if index >= 1 => match stack.copy(index as usize) {
+                copy @ (AbstractValue::VALUE | AbstractValue::INTEGER(_)) => Ok(Some(stack.push(copy))),
+                AbstractValue::SEQUENCE(_) | AbstractValue::EITHER(..) => Ok(None),
+            },
+            _ => Ok(Some(stack.push(AbstractValue::VALUE))),
+        },
+        SPECIAL(SEL) => match stack.pop(SEL, 3)?.as_slice() {
+            [_, then, otherwise] => Ok(Some(stack.push(then.to_owned().join(otherwise.to_owned())))),
+            _ => Ok(None),
+        },
+        SPECIAL(function) => match function.effect() {
+            Some((inputs, outputs)) => {
+                stack.pop(*function, inputs)?;
+                Ok(Some((0..outputs).fold(stack, |stack, _| stack.push(AbstractValue::VALUE))))
+            },
+            // the count of a repeat, the arguments of a native, ... are at least needed
+            None => match function {
+                REPEAT | ITERATION(_) | TRY | MAP => { stack.pop(*function, 2)?; Ok(None) },
+                FOLD | FOLDING | MAPPING(_) => { stack.pop(*function, 3)?; Ok(None) },
+                THROW => { stack.pop(*function, 1)?; Ok(None) },
+                _ => Ok(None),
+            },
+        },
+        INTEGER(value) => Ok(Some(stack.push(AbstractValue::INTEGER(*value)))),
+        RATIONAL(_) | BOOLEAN(_) | STRING(_) | LIST(_) => Ok(Some(stack.push(AbstractValue::VALUE))),
+    }
+}
+
+// effect of a sequence, None when it depends on values only known at run time
+pub fn sequence_effect(commands: &[PostfixCommand]) -> Option<StackEffect> {
+    match analyze(commands, AbstractStack { values: Vec::new(), unknown: 0, borrowed: 0, closed: false }) {
+        Ok(Some(stack)) => Some(StackEffect { inputs: stack.borrowed, outputs: stack.height() }),
+        Ok(None) | Err(_) => None,
+    }
+}
+
+pub fn command_effect(command: &PostfixCommand) -> Option<StackEffect> {
+    sequence_effect(std::slice::from_ref(command))
+}
+
+// rejects a program that underflows however it is run with the number of arguments it declares
+pub fn check_program(program: &PostfixProgram) -> Result<(), PostfixError> {
+    let arguments = program.paramsize as usize + program.variadic as usize;
+    // a variadic program may be given more arguments than it declares
+    let stack = AbstractStack { values: Vec::new(), unknown: arguments, borrowed: 0, closed: !program.variadic };
+    analyze(&program.commands, stack).map(|_| ())
+}
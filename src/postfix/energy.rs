@@ -0,0 +1,31 @@
+use super::{
+    enums::{
+        PostfixCommand,
+        PostfixFunction,
+        PostfixProgram,
+    },
+};
+
+
+// energy of a command still to be run: one for the step that runs it, plus the energy of the commands of a
+// sequence, which it keeps on the stack until an exec moves them back in front of the program. Every step
+// of core PostFix uses up at least one unit, as integers on the stack carry none and sequences cannot be
+// copied. None when the command may run its body any number of times, or bring back values already used.
+fn command_energy(command: &PostfixCommand) -> Option<usize> {
+    use PostfixCommand::*;
+    use PostfixFunction::*;
+    match command {
+        EXECUTE(commands) => sequence_energy(commands).map(|energy| energy + 1),
+        SPECIAL(REPEAT | ITERATION(_) | MAP | MAPPING(_) | FOLD | FOLDING | GET | PUSH | TRY) => None,
+        SPECIAL(_) | INTEGER(_) | RATIONAL(_) | BOOLEAN(_) | STRING(_) | LIST(_) => Some(1),
+    }
+}
+
+pub fn sequence_energy(commands: &[PostfixCommand]) -> Option<usize> {
+    commands.iter().try_fold(0, |energy, command| command_energy(command).map(|command| energy + command))
+}
+
+// most steps a run of the program can take, whatever its arguments, None when they are not bounded
+pub fn step_bound(program: &PostfixProgram) -> Option<usize> {
+    sequence_energy(&program.commands)
+}
@@ -1,4 +1,49 @@
-use super::errors::PostfixError;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    path::{
+        Path,
+        PathBuf,
+    },
+};
+
+use num::{
+    rational::Rational64,
+    traits::{
+        CheckedAdd,
+        CheckedDiv,
+        CheckedMul,
+        CheckedSub,
+    },
+    Signed,
+    Zero,
+};
+
+use super::{
+    config::{
+        PostfixConfig,
+        PostfixExtension,
+        PostfixOverflow,
+        PostfixTyping,
+    },
+    errors::PostfixError,
+    io::{
+        HostIo,
+        StandardIo,
+    },
+    lexer::{
+        parse_code_to_tokens,
+        Located,
+        Position,
+        Token,
+    },
+    modules::{
+        find_module,
+        FileSystemLoader,
+        ModuleLoader,
+    },
+};
 
 
 #[derive(Debug,Copy,Clone,PartialEq)]
@@ -11,130 +56,892 @@ pub enum PostfixArithmetic {
     MUL,
     SUB,
     REM,
+    // extended arithmetic, whose bitwise operators are spelled apart from the logical `and` and `or` of the prelude
+    BAND,
+    BOR,
+    BXOR,
+    GE,
+    LE,
+    MAX,
+    MIN,
+    NE,
+    POW,
+    SHL,
+    SHR,
+}
+
+impl PostfixArithmetic {
+    pub fn name(&self) -> &'static str {
+        use PostfixArithmetic::*;
+        match self {
+            ADD => "add", DIV => "div", EQ => "eq", GT => "gt", LT => "lt", MUL => "mul", SUB => "sub", REM => "rem",
+            BAND => "band", BOR => "bor", BXOR => "bxor", GE => "ge", LE => "le", MAX => "max", MIN => "min", NE => "ne",
+            POW => "pow", SHL => "shl", SHR => "shr",
+        }
+    }
+
+    // outcome of a comparison operator given how its operands are ordered, None for other operators
+    fn compares(&self, ordering: Ordering) -> Option<bool> {
+        use PostfixArithmetic::*;
+        match self {
+            EQ => Some(ordering == Ordering::Equal),
+            NE => Some(ordering != Ordering::Equal),
+            GT => Some(ordering == Ordering::Greater),
+            GE => Some(ordering != Ordering::Less),
+            LT => Some(ordering == Ordering::Less),
+            LE => Some(ordering != Ordering::Greater),
+            ADD | DIV | MUL | SUB | REM | BAND | BOR | BXOR | MAX | MIN | POW | SHL | SHR => None,
+        }
+    }
 }
 
 #[derive(Debug,Copy,Clone,PartialEq)]
 pub enum PostfixFunction {
+    ABS,
     ARITHMETIC(PostfixArithmetic),
+    AT,
+    CONCAT,
+    EMIT,
     EXEC,
+    FOLD,
+    // a fold going on with the accumulator under the items still to go, never written in source
+    FOLDING,
+    GET,
+    INT,
+    // a repeat going on from the given iteration, never written in source
+    ITERATION(usize),
+    LEN,
+    MAP,
+    // a map going on above the given stack height, never written in source
+    MAPPING(usize),
+    NATIVE(usize),
+    NEG,
     NGET,
     POP,
+    PRINT,
+    PUSH,
+    PUT,
+    READ,
+    REPEAT,
     SEL,
+    STR,
     SWAP,
+    THROW,
+    TRY,
+}
+
+impl PostfixFunction {
+    // extensions providing the function, none for core functions
+    pub fn extensions(&self) -> &'static [PostfixExtension] {
+        use PostfixFunction::*;
+        match self {
+            ARITHMETIC(PostfixArithmetic::ADD | PostfixArithmetic::DIV | PostfixArithmetic::EQ | PostfixArithmetic::GT
+                | PostfixArithmetic::LT | PostfixArithmetic::MUL | PostfixArithmetic::SUB | PostfixArithmetic::REM) => &[],
+            EXEC | NGET | POP | SEL | SWAP => &[],
+            NATIVE(_) => &[],
+            ABS | NEG | ARITHMETIC(_) => &[PostfixExtension::MATH],
+            REPEAT | ITERATION(_) => &[PostfixExtension::LOOP],
+            GET | PUT => &[PostfixExtension::MEMORY],
+            EMIT | PRINT | READ => &[PostfixExtension::IO],
+            THROW | TRY => &[PostfixExtension::EXCEPTION],
+            CONCAT | INT | STR => &[PostfixExtension::STRING],
+            LEN => &[PostfixExtension::STRING, PostfixExtension::LIST],
+            AT | FOLD | FOLDING | MAP | MAPPING(_) | PUSH => &[PostfixExtension::LIST],
+        }
+    }
+
+    // inputs consumed and outputs produced, None when they depend on the values the function is applied to
+    pub fn effect(&self) -> Option<(usize, usize)> {
+        use PostfixFunction::*;
+        match self {
+            ARITHMETIC(_) | AT | CONCAT | PUSH => Some((2, 1)),
+            ABS | GET | INT | LEN | NEG | NGET | STR => Some((1, 1)),
+            EMIT | POP | PRINT => Some((1, 0)),
+            PUT => Some((2, 0)),
+            READ => Some((0, 1)),
+            SEL => Some((3, 1)),
+            SWAP => Some((2, 2)),
+            EXEC | FOLD | FOLDING | ITERATION(_) | MAP | MAPPING(_) | NATIVE(_) | REPEAT | THROW | TRY => None,
+        }
+    }
+
+    // how the function is spelled in source, natives are named by the host instead
+    pub fn name(&self) -> Option<&'static str> {
+        use PostfixFunction::*;
+        match self {
+            ARITHMETIC(operator) => Some(operator.name()),
+            NATIVE(_) | FOLDING | ITERATION(_) | MAPPING(_) => None,
+            AT => Some("at"), FOLD => Some("fold"), MAP => Some("map"), PUSH => Some("push"),
+            ABS => Some("abs"), CONCAT => Some("concat"), EMIT => Some("emit"), EXEC => Some("exec"), GET => Some("get"),
+            INT => Some("int"), LEN => Some("len"), NEG => Some("neg"), NGET => Some("nget"), POP => Some("pop"),
+            PRINT => Some("print"), PUT => Some("put"), READ => Some("read"), REPEAT => Some("repeat"), SEL => Some("sel"),
+            STR => Some("str"), SWAP => Some("swap"), THROW => Some("throw"), TRY => Some("try"),
+        }
+    }
 }
 
 #[derive(Debug,Clone,Copy,PartialEq)]
 pub enum PostfixKeyword {
     FUNCTION(PostfixFunction),
-    HEAD
+    BOOLEAN(bool),
+    HEAD,
+    DEF,
+    IMPORT,
 }
 
 
 #[derive(Debug,Clone,PartialEq)]
 pub enum PostfixCommand {
     INTEGER(i32),
+    RATIONAL(Rational64),
+    BOOLEAN(bool),
+    STRING(String),
+    LIST(Vec<PostfixCommand>),
     SPECIAL(PostfixFunction),
     EXECUTE(Vec<PostfixCommand>),
 }
 
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum PostfixValueKind {
+    INTEGER,
+    RATIONAL,
+    BOOLEAN,
+    STRING,
+    LIST,
+    FUNCTION,
+    SEQUENCE,
+}
+
+impl PostfixCommand {
+    pub fn kind(&self) -> PostfixValueKind {
+        use PostfixCommand::*;
+        match self {
+            INTEGER(_) => PostfixValueKind::INTEGER,
+            RATIONAL(_) => PostfixValueKind::RATIONAL,
+            BOOLEAN(_) => PostfixValueKind::BOOLEAN,
+            STRING(_) => PostfixValueKind::STRING,
+            LIST(_) => PostfixValueKind::LIST,
+            SPECIAL(_) => PostfixValueKind::FUNCTION,
+            EXECUTE(_) => PostfixValueKind::SEQUENCE,
+        }
+    }
+}
+
+// a variadic program takes at least `paramsize` arguments, their actual count is pushed on top of them;
+// a program read from source knows where its commands are written, by location, one derived from it does not
 #[derive(Debug)]
-pub struct PostfixProgram { pub paramsize: u32, pub commands: Vec<PostfixCommand> }
+pub struct PostfixProgram { pub paramsize: u32, pub variadic: bool, pub commands: Vec<PostfixCommand>, pub positions: HashMap<Vec<usize>, Position> }
+
+impl PostfixProgram {
+
+    pub fn new(paramsize: u32, variadic: bool, commands: Vec<PostfixCommand>) -> Self {
+        Self { paramsize, variadic, commands, positions: HashMap::new() }
+    }
+
+    // where the command at `location` is written, or else the innermost sequence holding it: the commands
+    // spliced in from a definition are written where its name is
+    pub fn position(&self, location: &[usize]) -> Option<Position> {
+        (1..=location.len()).rev().find_map(|length| self.positions.get(&location[..length]).copied())
+    }
+}
+
+// a command roughly as it is written in source; natives and internal functions have no spelling of their own
+impl fmt::Display for PostfixCommand {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        use PostfixCommand::*;
+        let spelled = |commands: &[PostfixCommand]| commands.iter().map(|command| command.to_string()).collect::<Vec<_>>().join(" ");
+        match self {
+            INTEGER(n) => write!(formatter, "{}", n),
+            RATIONAL(value) => write!(formatter, "{}", value),
+            BOOLEAN(b) => write!(formatter, "{}", b),
+            STRING(text) => write!(formatter, "{:?}", text),
+            LIST(commands) => write!(formatter, "[{}]", spelled(commands)),
+            SPECIAL(function) => write!(formatter, "{}", function.name().unwrap_or("native")),
+            EXECUTE(commands) => write!(formatter, "({})", spelled(commands)),
+        }
+    }
+}
+
+impl fmt::Display for PostfixProgram {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "(postfix {}{}", self.paramsize, match self.variadic { true => "+", false => "" })?;
+        self.commands.iter().try_for_each(|command| write!(formatter, " {}", command))?;
+        write!(formatter, ")")
+    }
+}
+
+// numbered cells living outside of the stack, shared by every nested exec
+#[derive(Debug,Clone,PartialEq)]
+pub struct PostfixMemory { cells: Vec<Option<PostfixCommand>> }
+
+impl PostfixMemory {
+
+    pub fn new(size: usize) -> Self {
+        Self { cells: vec![None; size] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    fn index(&self, index: i32) -> Result<usize, PostfixError> {
+        match usize::try_from(index) {
+            Ok(i) if i < self.cells.len() => Ok(i),
+            _ => Err(PostfixError::IndexOutOfRangeByMemory { index, size: self.cells.len() }),
+        }
+    }
+
+    pub fn get(&self, index: i32) -> Result<PostfixCommand, PostfixError> {
+        match &self.cells[self.index(index)?] {
+            Some(command) => Ok(command.clone()),
+            None => Err(PostfixError::UninitializedMemoryCell { index }),
+        }
+    }
+
+    pub fn put(&mut self, index: i32, command: PostfixCommand) -> Result<(), PostfixError> {
+        let i = self.index(index)?;
+        self.cells[i] = Some(command);
+        Ok(())
+    }
+}
 
-pub struct ProgramBuilder {
+// an active try: its body is running as long as more than `depth` commands remain
+#[derive(Debug,Clone,PartialEq)]
+pub struct PostfixHandler {
+    pub depth: usize,
+    pub handler: Vec<PostfixCommand>,
+    pub stack: Vec<PostfixCommand>,
+}
+
+// a configuration of the abstract machine: commands still to run (top is next), the stack, the memory
+// and the handlers of the try bodies being run (innermost last)
+#[derive(Debug,Clone,PartialEq)]
+pub struct PostfixState {
+    pub commands: Vec<PostfixCommand>,
+    pub stack: Vec<PostfixCommand>,
+    pub memory: PostfixMemory,
+    pub handlers: Vec<PostfixHandler>,
+}
+
+impl PostfixState {
+
+    // handlers whose try body ran to completion are no longer active
+    pub fn leave_finished_handlers(&mut self) {
+        while self.handlers.last().is_some_and(|handler| handler.depth >= self.commands.len()) {
+            self.handlers.pop();
+        }
+    }
+
+    // a catchable failure unwinds to the innermost handler: the stack is restored to what it was
+    // when the try started, the code of the failure is pushed and the handler runs in place of the body
+    pub fn recover(&mut self, error: PostfixError) -> Result<(), PostfixError> {
+        match (error.code(), self.handlers.pop()) {
+            (Some(code), Some(PostfixHandler { depth, handler, stack })) => {
+                self.commands.truncate(depth);
+                handler.into_iter().rev().for_each(|command| self.commands.push(command));
+                self.stack = stack;
+                self.stack.push(PostfixCommand::INTEGER(code));
+                Ok(())
+            },
+            (_, _) => Err(error),
+        }
+    }
+}
+
+// what the interpreter consults or acts upon besides the machine state
+pub struct PostfixContext<'a> {
+    pub config: &'a PostfixConfig,
+    pub io: &'a mut dyn HostIo,
+}
+
+// where a definition comes from: the prelude may be shadowed by any other source, never the other way around
+#[derive(Debug,Clone,PartialEq)]
+pub enum PostfixSource {
+    PRELUDE,
+    PROGRAM,
+    FILE(PathBuf),
+}
+
+// where the builder stands within a `(def name (...))` or `(import name)` form
+enum FormState {
+    NONE,
+    NAMING,
+    // the name being defined, along with where it is written
+    DEFINING(String, Position),
+    IMPORTING,
+    IMPORTED,
+}
+
+pub struct ProgramBuilder<'a> {
     paramsize: usize,
+    variadic: bool,
+    // names of the arguments, first argument first, and what is statically known of the stack at the
+    // end of the program so far: its height (None once unknown) and the lowest height it went down to
+    parameters: Vec<String>,
+    height: Option<usize>,
+    floor: usize,
+    config: &'a PostfixConfig,
     commands: Vec<PostfixCommand>,
     stack: Vec<Vec<PostfixCommand>>,
+    // list literals being read, innermost last
+    lists: Vec<Vec<PostfixCommand>>,
+    definitions: HashMap<String, (Vec<PostfixCommand>, PostfixSource, Position)>,
+    form: FormState,
+    source: PostfixSource,
+    // where the token being read starts in its source, where the sequences and lists being read start
+    // (innermost last), and where each command of the program is written
+    position: Position,
+    openings: Vec<Position>,
+    positions: HashMap<Vec<usize>, Position>,
+    loader: &'a dyn ModuleLoader,
+    // files being imported (for cycle detection) and files already imported (imported once only)
+    loading: Vec<PathBuf>,
+    loaded: Vec<PathBuf>,
 }
 
-impl ProgramBuilder {
+impl<'a> ProgramBuilder<'a> {
 
-    pub fn new(paramsize: usize) -> Self {
+    pub fn new(paramsize: usize, config: &'a PostfixConfig) -> Self {
         Self {
             paramsize,
+            variadic: false,
+            parameters: Vec::new(),
+            height: Some(paramsize),
+            floor: paramsize,
+            config,
             commands: Vec::new(),
             stack: Vec::new(),
+            lists: Vec::new(),
+            definitions: HashMap::new(),
+            form: FormState::NONE,
+            source: PostfixSource::PROGRAM,
+            position: Position::default(),
+            openings: Vec::new(),
+            positions: HashMap::new(),
+            loader: &FileSystemLoader,
+            loading: Vec::new(),
+            loaded: Vec::new(),
+        }
+    }
+
+    pub fn with_variadic_arguments(mut self) -> Self {
+        self.variadic = true;
+        self.height = None;
+        self
+    }
+
+    pub fn with_parameters(mut self, parameters: Vec<String>) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    pub fn with_loader(mut self, loader: &'a dyn ModuleLoader) -> Self {
+        self.loader = loader;
+        self
+    }
+
+    // the program is read from a file: its imports are resolved relative to it
+    pub fn with_file(mut self, path: &Path) -> Self {
+        let path = self.loader.resolve(path);
+        self.source = PostfixSource::FILE(path.to_owned());
+        self.loading.push(path);
+        self
+    }
+
+    pub fn with_prelude(self, tokens: Vec<Located<Token>>) -> Result<Self, PostfixError> {
+        self.consume_definitions(tokens, PostfixSource::PRELUDE)
+    }
+
+    // feeds another source into the builder; such a source may only define and import
+    fn consume_definitions(mut self, tokens: Vec<Located<Token>>, source: PostfixSource) -> Result<Self, PostfixError> {
+        let stack = std::mem::take(&mut self.stack);
+        let openings = std::mem::take(&mut self.openings);
+        let commands = std::mem::take(&mut self.commands);
+        let form = std::mem::replace(&mut self.form, FormState::NONE);
+        let outer_source = std::mem::replace(&mut self.source, source.clone());
+        let mut builder = tokens.into_iter().try_fold(self, |builder, token| builder.consume(token))?;
+        match (builder.commands.is_empty(), builder.stack.is_empty(), builder.lists.is_empty()) {
+            (true, true, true) => {
+                builder.stack = stack;
+                builder.openings = openings;
+                builder.commands = commands;
+                builder.form = form;
+                builder.source = outer_source;
+                Ok(builder)
+            },
+            _ => Err(PostfixError::OnlyDefinitionsAllowed(source)),
         }
     }
 
-    pub fn build(self: &Self) -> PostfixProgram {
-        PostfixProgram { paramsize: self.paramsize as u32, commands: self.commands.to_owned() }
+    fn import(mut self, name: String) -> Result<Self, PostfixError> {
+        let importer = match &self.source {
+            PostfixSource::FILE(path) => Some(path.as_path()),
+            PostfixSource::PRELUDE | PostfixSource::PROGRAM => None,
+        };
+        let (path, code) = find_module(&name, importer, &self.config.search_path, self.loader)?;
+        match (self.loading.contains(&path), self.loaded.contains(&path)) {
+            (true, _) => {
+                let mut cycle = self.loading.to_owned();
+                cycle.push(path);
+                Err(PostfixError::ImportCycle(cycle))
+            },
+            (false, true) => Ok(self),
+            (false, false) => {
+                self.loading.push(path.to_owned());
+                let mut builder = self.consume_definitions(parse_code_to_tokens(&code)?, PostfixSource::FILE(path.to_owned()))?;
+                builder.loading.pop();
+                builder.loaded.push(path);
+                Ok(builder)
+            },
+        }
+    }
+
+    pub fn build(&self) -> Result<PostfixProgram, PostfixError> {
+        match self.lists.is_empty() {
+            true => Ok(PostfixProgram { paramsize: self.paramsize as u32, variadic: self.variadic, commands: self.commands.to_owned(), positions: self.positions.to_owned() }),
+            false => Err(PostfixError::UnterminatedList),
+        }
     }
 
-    pub fn consume(mut self: Self, token: super::lexer::Token) -> Result<Self, PostfixError> {
-        use super::lexer::Token::*;
+    // follows the stack through the commands of the program itself, not those of sequences
+    fn track(&mut self, command: &PostfixCommand) {
         use PostfixCommand::*;
-        match token {
-            LEFT => { self.stack.push(Vec::new()); Ok(self) },
-            RIGHT => match self.stack.pop() {
-                None => Err(PostfixError::UnmatchedRightParenthese),
-                Some(current) => { 
-                    let execute = EXECUTE(current);
-                    match self.stack.pop() {
-                    None => self.commands.push(execute), 
-                    Some(mut parent) => { parent.push(execute); self.stack.push(parent) }
-                }; Ok(self) }
-            },
-            NUMBER(d) => {
-                let integer = INTEGER(d);
-                match self.stack.pop() {
-                None => { self.commands.push(integer); Ok(self) },
-                Some(mut current) => { current.push(integer); self.stack.push(current); Ok(self) }
-            }},
-            KEYWORD(PostfixKeyword::HEAD) => Err(PostfixError::InvalidPostfixKeyword(PostfixKeyword::HEAD)),
-            KEYWORD(PostfixKeyword::FUNCTION(func)) => {
-                let keyword = SPECIAL(func);
-                match self.stack.pop() {
-                    None => { self.commands.push(keyword); Ok(self) },
-                    Some(mut current) => { current.push(keyword); self.stack.push(current); Ok(self) }
-                }
-            }
+        match (command, self.height) {
+            (_, None) => (),
+            (SPECIAL(function), Some(height)) => match function.effect().and_then(|(inputs, outputs)| Some((height.checked_sub(inputs)?, outputs))) {
+                Some((remaining, outputs)) => { self.floor = self.floor.min(remaining); self.height = Some(remaining + outputs) },
+                None => self.height = None,
+            },
+            (_, Some(height)) => self.height = Some(height + 1),
+        }
+    }
+
+    // a parameter is fetched by nget from wherever it lies at this point, provided it is still there
+    fn parameter(self, index: usize, name: String) -> Result<Self, PostfixError> {
+        let position = self.parameters.len() - 1 - index;
+        match (self.stack.is_empty(), self.height) {
+            (true, Some(height)) => match self.floor > position {
+                true => Ok(self.push(PostfixCommand::INTEGER((height - position) as i32)).push(PostfixCommand::SPECIAL(PostfixFunction::NGET))),
+                false => Err(PostfixError::ConsumedParameter(name)),
+            },
+            (false, _) | (_, None) => Err(PostfixError::UndeterminedStackDepth(name)),
+        }
+    }
+
+    // where the next command pushed lies in the program, None when it goes into a list or a definition
+    fn location(&self) -> Option<Vec<usize>> {
+        match (self.lists.is_empty(), &self.form) {
+            (true, FormState::NONE) => Some(std::iter::once(self.commands.len()).chain(self.stack.iter().map(Vec::len)).collect()),
+            (_, _) => None,
+        }
+    }
+
+    // a sequence or a list being closed is written where it starts
+    fn close(&mut self) {
+        if let Some(position) = self.openings.pop() {
+            self.position = position;
+        }
+    }
+
+    fn push(mut self, command: PostfixCommand) -> Self {
+        if let Some(location) = self.location() {
+            self.positions.insert(location, self.position);
+        }
+        match self.lists.last_mut() {
+            Some(list) => list.push(command),
+            None => match self.stack.pop() {
+                None => { self.track(&command); self.commands.push(command) },
+                Some(mut current) => { current.push(command); self.stack.push(current) }
+            },
+        };
+        self
+    }
+
+    // a definition frame is closed: it must hold exactly the body of the definition
+    fn define(mut self, name: String, position: Position, frame: Vec<PostfixCommand>) -> Result<Self, PostfixError> {
+        use PostfixCommand::*;
+        match frame.as_slice() {
+            [EXECUTE(body)] => {
+                self.definitions.insert(name, (body.to_owned(), self.source.to_owned(), position));
+                self.form = FormState::NONE;
+                Ok(self)
+            },
+            _ => Err(PostfixError::MalformedDefinition),
+        }
+    }
+
+    // a token of the source, starting at `position`
+    pub fn consume(mut self, (token, position): (Token, Position)) -> Result<Self, PostfixError> {
+        self.position = position;
+        self.read(token)
+    }
+
+    fn read(mut self, token: Token) -> Result<Self, PostfixError> {
+        use Token::*;
+        use PostfixCommand::*;
+        use FormState::*;
+        match (&self.form, token) {
+            // a function outside of the dialect is no keyword, its spelling is free to be defined
+            (NAMING, KEYWORD(PostfixKeyword::FUNCTION(func))) if !self.config.supports(&func) => match func.name() {
+                Some(name) => self.read(NAME(name.to_string())),
+                None => Err(PostfixError::MalformedDefinition),
+            },
+            (NAMING, NAME(name)) => match self.definitions.get(&name) {
+                Some((_, source, _)) if *source == self.source => Err(PostfixError::Redefinition(name)),
+                Some((_, PostfixSource::PRELUDE, _)) | None => { self.form = DEFINING(name, self.position); Ok(self) },
+                Some((_, source, position)) => Err(PostfixError::DuplicateDefinition {
+                    name,
+                    first: (source.to_owned(), *position),
+                    second: (self.source.to_owned(), self.position),
+                }),
+            },
+            (NAMING, _) => Err(PostfixError::MalformedDefinition),
+            (IMPORTING, NAME(name)) => { self.form = IMPORTED; self.import(name) },
+            (IMPORTING, _) => Err(PostfixError::MalformedImport),
+            (IMPORTED, RIGHT) => { self.stack.pop(); self.close(); self.form = NONE; Ok(self) },
+            (IMPORTED, _) => Err(PostfixError::MalformedImport),
+            // arity markers only belong to the header
+            (_, STAR) => Err(PostfixError::InvalidCharacterError('*')),
+            (_, PLUS) => Err(PostfixError::InvalidCharacterError('+')),
+            (_, LEFTSQUARE) => match self.config.extensions.contains(&PostfixExtension::LIST) {
+                true => { self.lists.push(Vec::new()); self.openings.push(self.position); Ok(self) },
+                false => Err(PostfixError::LiteralNotInDialect(LIST(Vec::new()))),
+            },
+            (_, RIGHTSQUARE) => match self.lists.pop() {
+                Some(items) => { self.close(); Ok(self.push(LIST(items))) },
+                None => Err(PostfixError::UnmatchedRightBracket),
+            },
+            // a list literal holds literal values only
+            (_, LEFT | RIGHT | NAME(_) | KEYWORD(PostfixKeyword::FUNCTION(_) | PostfixKeyword::HEAD | PostfixKeyword::DEF | PostfixKeyword::IMPORT))
+                if !self.lists.is_empty() => Err(PostfixError::MalformedList),
+            (_, LEFT) => { self.stack.push(Vec::new()); self.openings.push(self.position); Ok(self) },
+            (_, RIGHT) => match (self.stack.pop(), self.stack.is_empty()) {
+                (None, _) => Err(PostfixError::UnmatchedRightParenthese),
+                (Some(current), true) => match std::mem::replace(&mut self.form, NONE) {
+                    DEFINING(name, position) => { self.close(); self.define(name, position, current) },
+                    NONE | NAMING | IMPORTING | IMPORTED => { self.close(); Ok(self.push(EXECUTE(current))) },
+                },
+                (Some(current), false) => { self.close(); Ok(self.push(EXECUTE(current))) },
+            },
+            (_, NUMBER(d)) => Ok(self.push(INTEGER(d))),
+            (_, KEYWORD(PostfixKeyword::BOOLEAN(value))) => { let typing = self.config.typing; Ok(self.push(truth(value, typing))) },
+            (_, DECIMAL(value)) => match self.config.extensions.contains(&PostfixExtension::RATIONAL) {
+                true => Ok(self.push(rational(value))),
+                false => Err(PostfixError::LiteralNotInDialect(RATIONAL(value))),
+            },
+            (_, Token::STRING(text)) => match self.config.extensions.contains(&PostfixExtension::STRING) {
+                true => Ok(self.push(PostfixCommand::STRING(text))),
+                false => Err(PostfixError::LiteralNotInDialect(PostfixCommand::STRING(text))),
+            },
+            (NONE, KEYWORD(PostfixKeyword::DEF)) => match self.stack.as_slice() {
+                [current] if current.is_empty() => { self.form = NAMING; Ok(self) },
+                _ => Err(PostfixError::MalformedDefinition),
+            },
+            (_, KEYWORD(PostfixKeyword::DEF)) => Err(PostfixError::MalformedDefinition),
+            (NONE, KEYWORD(PostfixKeyword::IMPORT)) => match self.stack.as_slice() {
+                [current] if current.is_empty() => { self.form = IMPORTING; Ok(self) },
+                _ => Err(PostfixError::MalformedImport),
+            },
+            (_, KEYWORD(PostfixKeyword::IMPORT)) => Err(PostfixError::MalformedImport),
+            (DEFINING(defined, _), NAME(name)) if *defined == name => Err(PostfixError::RecursiveDefinition(name)),
+            (_, NAME(name)) => match (self.parameters.iter().position(|parameter| *parameter == name), self.definitions.get(&name), self.config.natives.lookup(&name)) {
+                // parameters take precedence over definitions
+                (Some(index), _, _) => self.parameter(index, name),
+                // a defined name behaves exactly like its body spliced in place
+                (None, Some((body, _, _)), _) => { let body = body.clone(); Ok(body.into_iter().fold(self, |builder, command| builder.push(command))) },
+                (None, None, Some(index)) => Ok(self.push(SPECIAL(PostfixFunction::NATIVE(index)))),
+                (None, None, None) => Err(PostfixError::InvalidFunctionNameError(name)),
+            },
+            (_, KEYWORD(PostfixKeyword::HEAD)) => Err(PostfixError::InvalidPostfixKeyword(PostfixKeyword::HEAD)),
+            (_, KEYWORD(PostfixKeyword::FUNCTION(func))) if !self.config.supports(&func) => match func.name() {
+                Some(name) if self.definitions.contains_key(name) => self.read(NAME(name.to_string())),
+                Some(_) | None => Err(PostfixError::FunctionNotInDialect(func)),
+            },
+            (_, KEYWORD(PostfixKeyword::FUNCTION(func))) => Ok(self.push(SPECIAL(func))),
         }
     }
 }
 
-fn compute_arithmetic_function(arithmetic: &PostfixArithmetic, pre_operand: i32, post_operand: i32) -> i32 {
+// a truth value as the dialect represents it, see PostfixTyping
+fn truth(value: bool, typing: PostfixTyping) -> PostfixCommand {
+    match typing {
+        PostfixTyping::LOOSE => PostfixCommand::INTEGER(value as i32),
+        PostfixTyping::STRICT => PostfixCommand::BOOLEAN(value),
+    }
+}
+
+// the result of an integer operation that may overflow, see PostfixOverflow
+fn overflowing(function: PostfixFunction, checked: Option<i32>, wrapped: i32, overflow: PostfixOverflow) -> Result<PostfixCommand, PostfixError> {
+    match (checked, overflow) {
+        (Some(n), _) => Ok(PostfixCommand::INTEGER(n)),
+        (None, PostfixOverflow::WRAPPING) => Ok(PostfixCommand::INTEGER(wrapped)),
+        (None, PostfixOverflow::CHECKED) => Err(PostfixError::ArithmeticOverflow { function }),
+    }
+}
+
+fn compute_arithmetic_function(arithmetic: &PostfixArithmetic, pre_operand: i32, post_operand: i32, config: &PostfixConfig) -> Result<PostfixCommand, PostfixError> {
+    use PostfixArithmetic::*;
+    use PostfixCommand::INTEGER;
+    let function = PostfixFunction::ARITHMETIC(*arithmetic);
+    match (arithmetic, post_operand) {
+        // an error of the program rather than a panic of the interpreter, for rem as much as div
+        (DIV | REM, 0) => Err(PostfixError::DivideByZero),
+        (POW, exponent) if exponent < 0 => Err(PostfixError::NegativeExponent { exponent }),
+        (ADD, _) => overflowing(function, pre_operand.checked_add(post_operand), pre_operand.wrapping_add(post_operand), config.overflow),
+        (SUB, _) => overflowing(function, pre_operand.checked_sub(post_operand), pre_operand.wrapping_sub(post_operand), config.overflow),
+        (MUL, _) => overflowing(function, pre_operand.checked_mul(post_operand), pre_operand.wrapping_mul(post_operand), config.overflow),
+        (DIV, _) => overflowing(function, pre_operand.checked_div(post_operand), pre_operand.wrapping_div(post_operand), config.overflow),
+        (REM, _) => overflowing(function, pre_operand.checked_rem(post_operand), pre_operand.wrapping_rem(post_operand), config.overflow),
+        (POW, _) => overflowing(function, pre_operand.checked_pow(post_operand as u32), pre_operand.wrapping_pow(post_operand as u32), config.overflow),
+        // shifting by a negative amount or by the width of an integer or more overflows
+        (SHL, _) => overflowing(function, u32::try_from(post_operand).ok().and_then(|shift| pre_operand.checked_shl(shift)), pre_operand.wrapping_shl(post_operand as u32), config.overflow),
+        (SHR, _) => overflowing(function, u32::try_from(post_operand).ok().and_then(|shift| pre_operand.checked_shr(shift)), pre_operand.wrapping_shr(post_operand as u32), config.overflow),
+        (BAND, _) => Ok(INTEGER(pre_operand & post_operand)),
+        (BOR, _) => Ok(INTEGER(pre_operand | post_operand)),
+        (BXOR, _) => Ok(INTEGER(pre_operand ^ post_operand)),
+        (MAX, _) => Ok(INTEGER(pre_operand.max(post_operand))),
+        (MIN, _) => Ok(INTEGER(pre_operand.min(post_operand))),
+        (EQ | NE | GT | GE | LT | LE, _) => Ok(truth(arithmetic.compares(pre_operand.cmp(&post_operand)) == Some(true), config.typing)),
+    }
+}
+
+// a rational that is a whole number is an integer, as long as it fits
+fn rational(value: Rational64) -> PostfixCommand {
+    match (value.is_integer(), i32::try_from(value.to_integer())) {
+        (true, Ok(n)) => PostfixCommand::INTEGER(n),
+        (_, _) => PostfixCommand::RATIONAL(value),
+    }
+}
+
+// exact arithmetic on rationals, which overflows the same way whatever the overflow mode
+fn compute_rational_function(arithmetic: &PostfixArithmetic, pre_operand: Rational64, post_operand: Rational64, config: &PostfixConfig) -> Result<PostfixCommand, PostfixError> {
     use PostfixArithmetic::*;
+    let function = PostfixFunction::ARITHMETIC(*arithmetic);
+    let overflow = PostfixError::ArithmeticOverflow { function };
     match arithmetic {
-        ADD => pre_operand + post_operand,
-        SUB => pre_operand - post_operand,
-        MUL => pre_operand * post_operand,
-        DIV => pre_operand / post_operand,
-        REM => pre_operand % post_operand,
-        EQ => if pre_operand == post_operand {1} else {0},
-        GT => if pre_operand > post_operand {1} else {0},
-        LT => if pre_operand < post_operand {1} else {0},
+        ADD => pre_operand.checked_add(&post_operand).map(rational).ok_or(overflow),
+        SUB => pre_operand.checked_sub(&post_operand).map(rational).ok_or(overflow),
+        MUL => pre_operand.checked_mul(&post_operand).map(rational).ok_or(overflow),
+        DIV => match post_operand.is_zero() {
+            true => Err(PostfixError::DivideByZero),
+            false => pre_operand.checked_div(&post_operand).map(rational).ok_or(overflow),
+        },
+        MAX => Ok(rational(pre_operand.max(post_operand))),
+        MIN => Ok(rational(pre_operand.min(post_operand))),
+        EQ | NE | GT | GE | LT | LE => Ok(truth(arithmetic.compares(pre_operand.cmp(&post_operand)) == Some(true), config.typing)),
+        REM | BAND | BOR | BXOR | POW | SHL | SHR =>
+            Err(PostfixError::UnexpectedValueKind { function, expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::RATIONAL }),
+    }
+}
+
+// the next item of a fold run through the body, the items still to go being kept last first;
+// only the accumulator is left once there are none
+fn fold_item(remaining_commands: &mut Vec<PostfixCommand>, stack: &mut Vec<PostfixCommand>, commands: Vec<PostfixCommand>, accumulator: PostfixCommand, mut items: Vec<PostfixCommand>) {
+    use PostfixCommand::*;
+    stack.push(accumulator);
+    if let Some(item) = items.pop() {
+        remaining_commands.push(SPECIAL(PostfixFunction::FOLDING));
+        remaining_commands.push(EXECUTE(commands.to_owned()));
+        remaining_commands.push(LIST(items));
+        commands.into_iter().rev().for_each(|command| remaining_commands.push(command));
+        remaining_commands.push(item);
     }
 }
 
-fn execute_postfix_function(function: &PostfixFunction, remaining_commands: &mut Vec<PostfixCommand>, stack: &mut Vec<PostfixCommand>) -> Result<(), PostfixError> {
+// the next item of a map run through the body above `height`, the items still to go being kept last first;
+// the results are gathered into a list once there are none
+fn map_item(remaining_commands: &mut Vec<PostfixCommand>, stack: &mut Vec<PostfixCommand>, commands: Vec<PostfixCommand>, mut items: Vec<PostfixCommand>, results: Vec<PostfixCommand>, height: usize) {
+    use PostfixCommand::*;
+    match items.pop() {
+        Some(item) => {
+            remaining_commands.push(SPECIAL(PostfixFunction::MAPPING(height)));
+            remaining_commands.push(EXECUTE(commands.to_owned()));
+            remaining_commands.push(LIST(items));
+            remaining_commands.push(LIST(results));
+            commands.into_iter().rev().for_each(|command| remaining_commands.push(command));
+            remaining_commands.push(item);
+        },
+        None => stack.push(LIST(results)),
+    }
+}
+
+fn execute_postfix_function(function: &PostfixFunction, state: &mut PostfixState, context: &mut PostfixContext) -> Result<(), PostfixError> {
     use PostfixFunction::*;
     use PostfixCommand::*;
+    let PostfixState { commands: remaining_commands, stack, memory, handlers } = state;
     match function {
+        ABS => match stack.pop() {
+            Some(INTEGER(n)) => { stack.push(overflowing(ABS, n.checked_abs(), n.wrapping_abs(), context.config.overflow)?); Ok(()) },
+            Some(RATIONAL(value)) => { stack.push(rational(value.abs())); Ok(()) },
+            Some(other) => Err(PostfixError::UnexpectedValueKind { function: ABS, expected: PostfixValueKind::INTEGER, actual: other.kind() }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: ABS, expected_number_of_arguments: 1 }),
+        }
+
         ARITHMETIC(operator) => match (stack.pop(), stack.pop()) {
             (None, _) | (_, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: ARITHMETIC(*operator), expected_number_of_arguments: 2 }),
-            (Some(INTEGER(post_operand)), Some(INTEGER(pre_operand))) => { 
-                match (operator, post_operand) {
-                    (PostfixArithmetic::DIV, 0) => Err(PostfixError::DivideByZero),
-                    (operator, post_operand) => {
-                        stack.push(INTEGER(compute_arithmetic_function(operator, pre_operand, post_operand))); Ok(())
-                    },
-                }
+            (Some(INTEGER(post_operand)), Some(INTEGER(pre_operand))) => match (operator, context.config.extensions.contains(&PostfixExtension::RATIONAL)) {
+                // division is exact in the rational dialect
+                (PostfixArithmetic::DIV, true) => {
+                    stack.push(compute_rational_function(operator, Rational64::from(pre_operand as i64), Rational64::from(post_operand as i64), context.config)?); Ok(())
+                },
+                (_, _) => { stack.push(compute_arithmetic_function(operator, pre_operand, post_operand, context.config)?); Ok(()) },
+            }
+            (Some(RATIONAL(post_operand)), Some(RATIONAL(pre_operand))) => {
+                stack.push(compute_rational_function(operator, pre_operand, post_operand, context.config)?); Ok(())
+            }
+            (Some(RATIONAL(post_operand)), Some(INTEGER(pre_operand))) => {
+                stack.push(compute_rational_function(operator, Rational64::from(pre_operand as i64), post_operand, context.config)?); Ok(())
             }
+            (Some(INTEGER(post_operand)), Some(RATIONAL(pre_operand))) => {
+                stack.push(compute_rational_function(operator, pre_operand, Rational64::from(post_operand as i64), context.config)?); Ok(())
+            }
+            (Some(STRING(post_operand)), Some(STRING(pre_operand))) => match operator.compares(pre_operand.cmp(&post_operand)) {
+                Some(outcome) => { stack.push(truth(outcome, context.config.typing)); Ok(()) },
+                None => Err(PostfixError::UnexpectedValueKind { function: ARITHMETIC(*operator), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::STRING }),
+            },
+            // booleans can only be compared for equality and combined logically
+            (Some(BOOLEAN(post_operand)), Some(BOOLEAN(pre_operand))) => match operator {
+                PostfixArithmetic::EQ => { stack.push(BOOLEAN(pre_operand == post_operand)); Ok(()) },
+                PostfixArithmetic::NE => { stack.push(BOOLEAN(pre_operand != post_operand)); Ok(()) },
+                PostfixArithmetic::BAND => { stack.push(BOOLEAN(pre_operand && post_operand)); Ok(()) },
+                PostfixArithmetic::BOR => { stack.push(BOOLEAN(pre_operand || post_operand)); Ok(()) },
+                PostfixArithmetic::BXOR => { stack.push(BOOLEAN(pre_operand ^ post_operand)); Ok(()) },
+                PostfixArithmetic::ADD | PostfixArithmetic::DIV | PostfixArithmetic::GT | PostfixArithmetic::LT
+                | PostfixArithmetic::MUL | PostfixArithmetic::SUB | PostfixArithmetic::REM | PostfixArithmetic::GE
+                | PostfixArithmetic::LE | PostfixArithmetic::MAX | PostfixArithmetic::MIN | PostfixArithmetic::POW
+                | PostfixArithmetic::SHL | PostfixArithmetic::SHR =>
+                    Err(PostfixError::UnexpectedValueKind { function: ARITHMETIC(*operator), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::BOOLEAN }),
+            },
+            (Some(value @ (STRING(_) | BOOLEAN(_))), Some(INTEGER(_) | RATIONAL(_) | STRING(_) | BOOLEAN(_)))
+            | (Some(INTEGER(_) | RATIONAL(_)), Some(value @ (STRING(_) | BOOLEAN(_)))) =>
+                Err(PostfixError::UnexpectedValueKind { function: ARITHMETIC(*operator), expected: PostfixValueKind::INTEGER, actual: value.kind() }),
             _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: ARITHMETIC(*operator) })
         }
+
+        AT => match (stack.pop(), stack.pop()) {
+            (None, _) | (_, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: AT, expected_number_of_arguments: 2 }),
+            (Some(INTEGER(index)), Some(LIST(items))) => match usize::try_from(index) {
+                Ok(i) if i > 0 && i <= items.len() => { stack.push(items[i - 1].to_owned()); Ok(()) },
+                _ => Err(PostfixError::IndexOutOfRangeByATFunction { index, min: 1, max: items.len() }),
+            },
+            (Some(INTEGER(_)), Some(other)) => Err(PostfixError::UnexpectedValueKind { function: AT, expected: PostfixValueKind::LIST, actual: other.kind() }),
+            (Some(other), Some(_)) => Err(PostfixError::UnexpectedValueKind { function: AT, expected: PostfixValueKind::INTEGER, actual: other.kind() }),
+        }
+
+        CONCAT => match (stack.pop(), stack.pop()) {
+            (None, _) | (_, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: CONCAT, expected_number_of_arguments: 2 }),
+            (Some(STRING(post_operand)), Some(STRING(pre_operand))) => { stack.push(STRING(pre_operand + &post_operand)); Ok(()) },
+            (Some(STRING(_)), Some(other)) | (Some(other), Some(_)) => Err(PostfixError::UnexpectedValueKind { function: CONCAT, expected: PostfixValueKind::STRING, actual: other.kind() }),
+        }
        
+        EMIT => match stack.pop() {
+            Some(INTEGER(code)) => match u32::try_from(code).ok().and_then(char::from_u32) {
+                Some(character) => { context.io.write(&character.to_string()); Ok(()) },
+                None => Err(PostfixError::InvalidCharacterCode(code)),
+            },
+            Some(_) => Err(PostfixError::WrongTypeOfFunctionArguments { function: EMIT }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: EMIT, expected_number_of_arguments: 1 }),
+        }
+
         EXEC => match stack.pop() {
             None => Err(PostfixError::WrongNumberOfFunctionArguments { function: EXEC, expected_number_of_arguments: 1 }),
             Some(EXECUTE(commands)) => { commands.iter().rev().fold((), |(), command| remaining_commands.push(command.to_owned())); Ok(()) },
             _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: EXEC })
         }
        
+        FOLD => match (stack.pop(), stack.pop(), stack.pop()) {
+            (None, _, _) | (_, None, _) | (_, _, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: FOLD, expected_number_of_arguments: 3 }),
+            (Some(EXECUTE(commands)), Some(initial), Some(LIST(mut items))) => {
+                items.reverse();
+                fold_item(remaining_commands, stack, commands, initial, items);
+                Ok(())
+            },
+            (Some(EXECUTE(_)), Some(_), Some(other)) => Err(PostfixError::UnexpectedValueKind { function: FOLD, expected: PostfixValueKind::LIST, actual: other.kind() }),
+            (Some(other), Some(_), Some(_)) => Err(PostfixError::UnexpectedValueKind { function: FOLD, expected: PostfixValueKind::SEQUENCE, actual: other.kind() }),
+        }
+
+        FOLDING => match (stack.pop(), stack.pop(), stack.pop()) {
+            (Some(EXECUTE(commands)), Some(LIST(items)), Some(accumulator)) => { fold_item(remaining_commands, stack, commands, accumulator, items); Ok(()) },
+            (None, _, _) | (_, None, _) | (_, _, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: FOLDING, expected_number_of_arguments: 3 }),
+            _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: FOLDING }),
+        }
+
+        GET => match stack.pop() {
+            Some(INTEGER(index)) => { stack.push(memory.get(index)?); Ok(()) },
+            Some(_) => Err(PostfixError::WrongTypeOfFunctionArguments { function: GET }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: GET, expected_number_of_arguments: 1 }),
+        }
+
+        INT => match stack.pop() {
+            Some(STRING(text)) => match text.trim().parse() {
+                Ok(n) => { stack.push(INTEGER(n)); Ok(()) },
+                Err(_) => Err(PostfixError::InvalidIntegerString(text)),
+            },
+            Some(other) => Err(PostfixError::UnexpectedValueKind { function: INT, expected: PostfixValueKind::STRING, actual: other.kind() }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: INT, expected_number_of_arguments: 1 }),
+        }
+
+        LEN => match stack.pop() {
+            Some(STRING(text)) => { stack.push(INTEGER(text.chars().count() as i32)); Ok(()) },
+            Some(LIST(items)) => { stack.push(INTEGER(items.len() as i32)); Ok(()) },
+            Some(other) => Err(PostfixError::UnexpectedValueKind { function: LEN, expected: PostfixValueKind::STRING, actual: other.kind() }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: LEN, expected_number_of_arguments: 1 }),
+        }
+
+        MAP => match (stack.pop(), stack.pop()) {
+            (None, _) | (_, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: MAP, expected_number_of_arguments: 2 }),
+            (Some(EXECUTE(commands)), Some(LIST(mut items))) => {
+                items.reverse();
+                map_item(remaining_commands, stack, commands, items, Vec::new(), stack.len());
+                Ok(())
+            },
+            (Some(EXECUTE(_)), Some(other)) => Err(PostfixError::UnexpectedValueKind { function: MAP, expected: PostfixValueKind::LIST, actual: other.kind() }),
+            (Some(other), Some(_)) => Err(PostfixError::UnexpectedValueKind { function: MAP, expected: PostfixValueKind::SEQUENCE, actual: other.kind() }),
+        }
+
+        // the body of a map takes its item and leaves exactly one result in its place
+        MAPPING(height) => match (stack.pop(), stack.pop(), stack.pop()) {
+            (Some(EXECUTE(commands)), Some(LIST(items)), Some(LIST(mut results))) => match stack.len() == height + 1 {
+                true => {
+                    results.extend(stack.pop());
+                    map_item(remaining_commands, stack, commands, items, results, *height);
+                    Ok(())
+                },
+                false => Err(PostfixError::UnbalancedMapBody { expected_stack_height: height + 1, actual_stack_height: stack.len() }),
+            },
+            (None, _, _) | (_, None, _) | (_, _, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: MAPPING(*height), expected_number_of_arguments: 3 }),
+            _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: MAPPING(*height) }),
+        }
+
+        NATIVE(index) => match context.config.natives.get(*index) {
+            None => Err(PostfixError::UnregisteredNative(*index)),
+            Some(native) if stack.len() < native.arity => Err(PostfixError::WrongNumberOfNativeArguments { name: native.name.to_owned(), expected_number_of_arguments: native.arity }),
+            Some(native) => {
+                let arguments: Option<Vec<i32>> = stack.split_off(stack.len() - native.arity).into_iter().map(|command| match command {
+                    INTEGER(n) => Some(n),
+                    _ => None,
+                }).collect();
+                match arguments.map(|arguments| (native.function)(&arguments)) {
+                    None => Err(PostfixError::WrongTypeOfNativeArguments { name: native.name.to_owned() }),
+                    Some(Err(message)) => Err(PostfixError::NativeFunctionFailure { name: native.name.to_owned(), message }),
+                    Some(Ok(results)) => { stack.extend(results.into_iter().map(INTEGER)); Ok(()) },
+                }
+            },
+        }
+
+        NEG => match stack.pop() {
+            Some(INTEGER(n)) => { stack.push(overflowing(NEG, n.checked_neg(), n.wrapping_neg(), context.config.overflow)?); Ok(()) },
+            Some(RATIONAL(value)) => { stack.push(rational(-value)); Ok(()) },
+            Some(other) => Err(PostfixError::UnexpectedValueKind { function: NEG, expected: PostfixValueKind::INTEGER, actual: other.kind() }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: NEG, expected_number_of_arguments: 1 }),
+        }
+
         NGET => match stack.pop() {
             Some(INTEGER(n)) => {
                 match usize::try_from(n) {
                     Ok(n) if n > 0 => match (stack.len() as i32 - n as i32) >= 0  {
                         true => match stack.get(stack.len() - n) {
-                            Some(INTEGER(v)) => { let v = v.to_owned(); stack.push(INTEGER(v)); Ok(()) },
+                            Some(value @ (INTEGER(_) | RATIONAL(_) | BOOLEAN(_) | STRING(_) | LIST(_))) => { let value = value.to_owned(); stack.push(value); Ok(()) },
                             Some(command) => Err(PostfixError::InvalidValueByNGETFunction { command: command.clone() }),
                             _ => Err(PostfixError::IndexOutOfRangeByNGETFunction { index: n as i32, min: 1, max: stack.len() }),
                         },
@@ -153,25 +960,108 @@ fn execute_postfix_function(function: &PostfixFunction, remaining_commands: &mut
             _ => Err(PostfixError::WrongNumberOfFunctionArguments { function: POP, expected_number_of_arguments: 1 }),
         },
         
+        PRINT => match stack.pop() {
+            Some(INTEGER(n)) => { context.io.write(&format!("{}\n", n)); Ok(()) },
+            Some(RATIONAL(value)) => { context.io.write(&format!("{}\n", value)); Ok(()) },
+            Some(BOOLEAN(value)) => { context.io.write(&format!("{}\n", value)); Ok(()) },
+            Some(STRING(text)) => { context.io.write(&format!("{}\n", text)); Ok(()) },
+            Some(_) => Err(PostfixError::WrongTypeOfFunctionArguments { function: PRINT }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: PRINT, expected_number_of_arguments: 1 }),
+        }
+
+        PUSH => match (stack.pop(), stack.pop()) {
+            (None, _) | (_, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: PUSH, expected_number_of_arguments: 2 }),
+            (Some(item), Some(LIST(mut items))) => { items.push(item); stack.push(LIST(items)); Ok(()) },
+            (Some(_), Some(other)) => Err(PostfixError::UnexpectedValueKind { function: PUSH, expected: PostfixValueKind::LIST, actual: other.kind() }),
+        }
+
+        PUT => match (stack.pop(), stack.pop()) {
+            (None, _) | (_, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: PUT, expected_number_of_arguments: 2 }),
+            (Some(INTEGER(index)), Some(command)) => memory.put(index, command),
+            _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: PUT }),
+        }
+
+        READ => match context.io.read() {
+            Some(n) => { stack.push(INTEGER(n)); Ok(()) },
+            None => Err(PostfixError::EndOfInput),
+        }
+
+        REPEAT | ITERATION(_) => match (stack.pop(), stack.pop()) {
+            (None, _) | (_, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: *function, expected_number_of_arguments: 2 }),
+            (Some(EXECUTE(commands)), Some(INTEGER(count))) => {
+                let counter = match function {
+                    ITERATION(counter) => *counter,
+                    _ => 1,
+                };
+                match usize::try_from(count) {
+                    // one iteration runs with its counter on top of the stack, then the loop goes on from the next
+                    Ok(count) if counter <= count => {
+                        remaining_commands.push(SPECIAL(ITERATION(counter + 1)));
+                        remaining_commands.push(EXECUTE(commands.to_owned()));
+                        remaining_commands.push(INTEGER(count as i32));
+                        commands.into_iter().rev().for_each(|command| remaining_commands.push(command));
+                        remaining_commands.push(INTEGER(counter as i32));
+                        Ok(())
+                    },
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(PostfixError::NegativeRepeatCount { count }),
+                }
+            },
+            _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: *function }),
+        }
+
         SEL => match (stack.pop(), stack.pop(), stack.pop()) {
             (None, _, _) | (_, None, _) | (_, _, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: SEL, expected_number_of_arguments: 3 }),
-            (Some(command), _, Some(INTEGER(0))) => { stack.push(command); Ok(()) },
-            (_, Some(command), Some(INTEGER(_))) => { stack.push(command); Ok(()) },
-            _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: SEL }),
+            (Some(otherwise), Some(then), Some(condition)) => match (context.config.typing, condition) {
+                (_, BOOLEAN(false)) | (PostfixTyping::LOOSE, INTEGER(0)) => { stack.push(otherwise); Ok(()) },
+                (_, BOOLEAN(true)) | (PostfixTyping::LOOSE, INTEGER(_)) => { stack.push(then); Ok(()) },
+                (PostfixTyping::STRICT, INTEGER(_)) =>
+                    Err(PostfixError::UnexpectedValueKind { function: SEL, expected: PostfixValueKind::BOOLEAN, actual: PostfixValueKind::INTEGER }),
+                (_, RATIONAL(_) | STRING(_) | LIST(_) | SPECIAL(_) | EXECUTE(_)) => Err(PostfixError::WrongTypeOfFunctionArguments { function: SEL }),
+            },
         }
         
+        STR => match stack.pop() {
+            Some(INTEGER(n)) => { stack.push(STRING(n.to_string())); Ok(()) },
+            Some(other) => Err(PostfixError::UnexpectedValueKind { function: STR, expected: PostfixValueKind::INTEGER, actual: other.kind() }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: STR, expected_number_of_arguments: 1 }),
+        }
+
         SWAP => match (stack.pop(), stack.pop()) {
             (Some(command1), Some(command2)) => { stack.push(command1); stack.push(command2); Ok(()) },
             _ => Err(PostfixError::WrongNumberOfFunctionArguments { function: SWAP, expected_number_of_arguments: 2 }),
         },
+
+        THROW => match stack.pop() {
+            Some(INTEGER(code)) => Err(PostfixError::Thrown(code)),
+            Some(_) => Err(PostfixError::WrongTypeOfFunctionArguments { function: THROW }),
+            None => Err(PostfixError::WrongNumberOfFunctionArguments { function: THROW, expected_number_of_arguments: 1 }),
+        },
+
+        TRY => match (stack.pop(), stack.pop()) {
+            (None, _) | (_, None) => Err(PostfixError::WrongNumberOfFunctionArguments { function: TRY, expected_number_of_arguments: 2 }),
+            (Some(EXECUTE(handler)), Some(EXECUTE(body))) => {
+                handlers.push(PostfixHandler { depth: remaining_commands.len(), handler, stack: stack.to_owned() });
+                body.into_iter().rev().for_each(|command| remaining_commands.push(command));
+                Ok(())
+            },
+            _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: TRY }),
+        },
     }
 }
 
-pub fn execute_command(command: &PostfixCommand, remaining_commands: &mut Vec<PostfixCommand>, stack: &mut Vec<PostfixCommand>) -> Result<(), PostfixError> {
+pub fn execute_command(command: &PostfixCommand, state: &mut PostfixState, context: &mut PostfixContext) -> Result<(), PostfixError> {
     use PostfixCommand::*;
     match command {
 
-        SPECIAL(command) => { execute_postfix_function(command, remaining_commands, stack) }
-        command => { stack.push(command.clone()); Ok(())}
+        SPECIAL(command) => { execute_postfix_function(command, state, context) }
+        command => { state.stack.push(command.clone()); Ok(())}
     }
-}
\ No newline at end of file
+}
+
+// the values left by `function` run on `operands` alone, for a function that only computes from them
+pub fn apply_function(function: PostfixFunction, operands: &[PostfixCommand], config: &PostfixConfig) -> Result<Vec<PostfixCommand>, PostfixError> {
+    let mut state = PostfixState { commands: Vec::new(), stack: operands.to_owned(), memory: PostfixMemory::new(0), handlers: Vec::new() };
+    let mut context = PostfixContext { config, io: &mut StandardIo };
+    execute_postfix_function(&function, &mut state, &mut context).map(|()| state.stack)
+}
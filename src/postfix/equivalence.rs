@@ -0,0 +1,163 @@
+use std::mem::discriminant;
+
+use super::{
+    config::PostfixConfig,
+    enums::PostfixProgram,
+    errors::PostfixError,
+    io::SilentIo,
+    programs::execute_program_counting_steps,
+    symbolic::argument_vectors,
+};
+
+
+// the arguments two programs are compared on: every vector of values from `low` to `high` when there are at
+// most `exhaustive_limit` of them, otherwise `samples` vectors drawn from the same range
+#[derive(Debug,Clone,PartialEq)]
+pub struct EquivalenceBounds {
+    pub low: i32,
+    pub high: i32,
+    pub exhaustive_limit: usize,
+    pub samples: usize,
+    pub seed: u64,
+}
+
+impl Default for EquivalenceBounds {
+    fn default() -> Self {
+        Self::new(-4, 4)
+    }
+}
+
+impl EquivalenceBounds {
+
+    pub fn new(low: i32, high: i32) -> Self {
+        Self { low: low.min(high), high: low.max(high), exhaustive_limit: 100_000, samples: 10_000, seed: 0x2545F4914F6CDD1D }
+    }
+
+    pub fn with_exhaustive_limit(mut self, exhaustive_limit: usize) -> Self {
+        self.exhaustive_limit = exhaustive_limit;
+        self
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // how many values lie within the bounds, without going through them
+    fn width(&self) -> u64 {
+        (self.high as i64 - self.low as i64 + 1) as u64
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub enum PostfixEquivalence {
+    // no difference on any of the `tested` vectors, which were all of them when `exhaustive`
+    EQUIVALENT { tested: usize, exhaustive: bool },
+    // the smallest arguments found on which the programs differ, with what each one gives
+    COUNTEREXAMPLE { arguments: Vec<i32>, left: Result<i32, PostfixError>, right: Result<i32, PostfixError> },
+}
+
+// xorshift, so that a sampled check gives the same answer from one run to the next
+struct Sampler(u64);
+
+impl Sampler {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn vector(&mut self, count: usize, bounds: &EquivalenceBounds) -> Vec<i32> {
+        (0..count).map(|_| (bounds.low as i64 + (self.next() % bounds.width()) as i64) as i32).collect()
+    }
+}
+
+// the same integer, or errors of the same kind whatever they carry
+pub fn same_outcome(left: &Result<i32, PostfixError>, right: &Result<i32, PostfixError>) -> bool {
+    match (left, right) {
+        (Ok(left), Ok(right)) => left == right,
+        (Err(left), Err(right)) => discriminant(left) == discriminant(right),
+        (_, _) => false,
+    }
+}
+
+// smaller arguments come first: by the sum of their magnitudes, then by the vector itself
+fn size(arguments: &[i32]) -> (u64, Vec<i32>) {
+    (arguments.iter().map(|argument| argument.unsigned_abs() as u64).sum(), arguments.to_owned())
+}
+
+struct Comparison<'a> {
+    left: &'a PostfixProgram,
+    right: &'a PostfixProgram,
+    // positions of the arguments of the left program the right one goes without, counted from 1
+    removed: &'a [usize],
+    config: &'a PostfixConfig,
+}
+
+impl Comparison<'_> {
+    fn run(&self, arguments: &[i32]) -> Option<PostfixEquivalence> {
+        let arguments = arguments.to_vec();
+        let (left, _) = execute_program_counting_steps(self.left, &arguments, self.config, &mut SilentIo);
+        let kept = arguments.iter().enumerate().filter(|(position, _)| !self.removed.contains(&(position + 1))).map(|(_, argument)| *argument).collect();
+        let (right, _) = execute_program_counting_steps(self.right, &kept, self.config, &mut SilentIo);
+        match same_outcome(&left, &right) {
+            true => None,
+            false => Some(PostfixEquivalence::COUNTEREXAMPLE { arguments, left, right }),
+        }
+    }
+
+    // a sampled counterexample with each argument moved towards zero for as long as the programs still differ
+    fn shrink(&self, mut counterexample: PostfixEquivalence, bounds: &EquivalenceBounds) -> PostfixEquivalence {
+        while let Some(smaller) = match &counterexample {
+            PostfixEquivalence::COUNTEREXAMPLE { arguments, .. } => (0..arguments.len()).flat_map(|position| {
+                let argument = arguments[position];
+                [0, argument / 2, argument - argument.signum()].into_iter().filter(move |value| size(&[*value]) < size(&[argument])).map(move |value| {
+                    let mut candidate = arguments.to_owned();
+                    candidate[position] = value;
+                    candidate
+                })
+            }).filter(|candidate| candidate.iter().all(|value| (bounds.low..=bounds.high).contains(value))).find_map(|candidate| self.run(&candidate)),
+            PostfixEquivalence::EQUIVALENT { .. } => None,
+        } {
+            counterexample = smaller;
+        }
+        counterexample
+    }
+}
+
+// whether the programs give the same integer or the same kind of error on every argument vector within
+// `bounds`, both being run on the same number of arguments with no input and their output dropped
+pub fn check_equivalence(left: &PostfixProgram, right: &PostfixProgram, bounds: &EquivalenceBounds, config: &PostfixConfig) -> Result<PostfixEquivalence, PostfixError> {
+    check_equivalence_without(left, right, &[], bounds, config)
+}
+
+// the same check with the right program given the arguments of the left one except those at `removed`
+pub fn check_equivalence_without(left: &PostfixProgram, right: &PostfixProgram, removed: &[usize], bounds: &EquivalenceBounds, config: &PostfixConfig) -> Result<PostfixEquivalence, PostfixError> {
+    let count = left.paramsize as usize;
+    let removed_count = (1..=count).filter(|position| removed.contains(position)).count();
+    if count != right.paramsize as usize + removed_count || removed_count != removed.len() {
+        return Err(PostfixError::MismatchedParameters { left: left.paramsize, right: right.paramsize });
+    }
+    let comparison = Comparison { left, right, removed, config };
+    match bounds.width().checked_pow(count as u32) {
+        Some(total) if total <= bounds.exhaustive_limit as u64 => {
+            let values: Vec<i32> = (bounds.low..=bounds.high).collect();
+            let mut vectors: Vec<Vec<i32>> = argument_vectors(count, &values).collect();
+            vectors.sort_by_key(|arguments| size(arguments));
+            Ok(vectors.iter().find_map(|arguments| comparison.run(arguments)).unwrap_or(PostfixEquivalence::EQUIVALENT { tested: total as usize, exhaustive: true }))
+        },
+        _ => {
+            let mut sampler = Sampler(bounds.seed.max(1));
+            match (0..bounds.samples).find_map(|_| comparison.run(&sampler.vector(count, bounds))) {
+                Some(counterexample) => Ok(comparison.shrink(counterexample, bounds)),
+                None => Ok(PostfixEquivalence::EQUIVALENT { tested: bounds.samples, exhaustive: false }),
+            }
+        },
+    }
+}
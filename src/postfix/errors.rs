@@ -1,28 +1,125 @@
+use std::path::PathBuf;
+
+use num::rational::Rational64;
+
 use super::{
     lexer::{
         Alphabet, 
+        Position,
     },
     enums::{
         PostfixKeyword, 
         PostfixFunction, 
-        PostfixCommand
+        PostfixCommand,
+        PostfixMemory,
+        PostfixSource,
+        PostfixValueKind,
     },
 };
 
-#[derive(Debug,PartialEq)]
+#[derive(Debug,Clone,PartialEq)]
 pub enum PostfixError {
     InvalidCharacterError(char),
     NumberLiteralFollowedByAlphabetError(i32, Alphabet),
     InvalidFunctionNameError(String),
     PostfixShouldBeginWithLeftPostfixAndEndWithRight,
+    MalformedParameters,
+    UndeterminedStackDepth(String),
+    ConsumedParameter(String),
     UnmatchedRightParenthese,
+    UnmatchedRightBracket,
+    UnterminatedList,
+    MalformedList,
     InvalidPostfixKeyword(PostfixKeyword),
+    FunctionNotInDialect(PostfixFunction),
+    MalformedDefinition,
+    Redefinition(String),
+    RecursiveDefinition(String),
+    UnsupportedPreludeVersion { requested: u32, available: u32 },
+    DuplicateDefinition { name: String, first: (PostfixSource, Position), second: (PostfixSource, Position) },
+    OnlyDefinitionsAllowed(PostfixSource),
+    MalformedImport,
+    ModuleNotFound { name: String, searched: Vec<PathBuf> },
+    ImportCycle(Vec<PathBuf>),
+    SourceNotFound(PathBuf),
     WrongNumberOfArguments{ expected: usize, actual: Vec<i32> },
     WrongNumberOfFunctionArguments{ function: PostfixFunction, expected_number_of_arguments: usize },
     WrongTypeOfFunctionArguments{ function: PostfixFunction },
+    StackUnderflow { function: PostfixFunction, expected_number_of_arguments: usize, available: usize },
+    // the command at `location` in the program, written at `position` in its source when it has one
+    MistypedArgument { function: PostfixFunction, location: Vec<usize>, position: Option<Position>, expected: PostfixValueKind, actual: PostfixValueKind },
     IndexOutOfRangeByNGETFunction { index: i32, min: usize, max: usize },
+    IndexOutOfRangeByATFunction { index: i32, min: usize, max: usize },
     InvalidValueByNGETFunction { command: PostfixCommand },
-    NonNumeralFinalState { command: PostfixCommand },
-    EmptyStackFinalState,
+    // the final state comes with the memory as the run left it
+    NonNumeralFinalState { command: PostfixCommand, memory: PostfixMemory },
+    EmptyStackFinalState { memory: PostfixMemory },
     DivideByZero,
+    ArithmeticOverflow { function: PostfixFunction },
+    NegativeExponent { exponent: i32 },
+    NegativeRepeatCount { count: i32 },
+    UnbalancedMapBody { expected_stack_height: usize, actual_stack_height: usize },
+    IndexOutOfRangeByMemory { index: i32, size: usize },
+    UninitializedMemoryCell { index: i32 },
+    InvalidCharacterCode(i32),
+    EndOfInput,
+    InvalidNativeName(String),
+    UnregisteredNative(usize),
+    // natives are named as the host registered them
+    NativeFunctionFailure { name: String, message: String },
+    WrongNumberOfNativeArguments { name: String, expected_number_of_arguments: usize },
+    WrongTypeOfNativeArguments { name: String },
+    Thrown(i32),
+    UnterminatedString,
+    InvalidEscapeError(char),
+    LiteralNotInDialect(PostfixCommand),
+    UnexpectedValueKind { function: PostfixFunction, expected: PostfixValueKind, actual: PostfixValueKind },
+    InvalidIntegerString(String),
+    InvalidDecimalLiteral,
+    UnrepresentableFinalState { value: Rational64 },
+    WrongNumberOfKnownArguments { expected: usize, actual: usize },
+    UnsupportedSpecialization,
+    UnsupportedSymbolicCommand(PostfixCommand),
+    UnsupportedSymbolicProgram,
+    MismatchedParameters { left: u32, right: u32 },
+    UnsupportedSuperoptimization,
+    // a fix whose result differs from the original program's on these arguments
+    UnverifiedFix { arguments: Vec<i32> },
+}
+
+impl PostfixError {
+    // code under which a runtime failure reaches the handler of a try, None when it cannot be caught
+    pub fn code(&self) -> Option<i32> {
+        use PostfixError::*;
+        match self {
+            Thrown(code) => Some(*code),
+            DivideByZero => Some(-1),
+            WrongNumberOfFunctionArguments { .. } | WrongNumberOfNativeArguments { .. } => Some(-2),
+            WrongTypeOfFunctionArguments { .. } | WrongTypeOfNativeArguments { .. } => Some(-3),
+            IndexOutOfRangeByNGETFunction { .. } => Some(-4),
+            InvalidValueByNGETFunction { .. } => Some(-5),
+            NegativeRepeatCount { .. } => Some(-6),
+            IndexOutOfRangeByMemory { .. } => Some(-7),
+            UninitializedMemoryCell { .. } => Some(-8),
+            InvalidCharacterCode(_) => Some(-9),
+            EndOfInput => Some(-10),
+            NativeFunctionFailure { .. } => Some(-11),
+            UnregisteredNative(_) => Some(-12),
+            UnexpectedValueKind { .. } => Some(-13),
+            InvalidIntegerString(_) => Some(-14),
+            ArithmeticOverflow { .. } => Some(-15),
+            NegativeExponent { .. } => Some(-16),
+            IndexOutOfRangeByATFunction { .. } => Some(-17),
+            UnbalancedMapBody { .. } => Some(-18),
+            InvalidCharacterError(_) | NumberLiteralFollowedByAlphabetError(..) | InvalidFunctionNameError(_)
+            | PostfixShouldBeginWithLeftPostfixAndEndWithRight | MalformedParameters | UndeterminedStackDepth(_) | ConsumedParameter(_) | StackUnderflow { .. } | MistypedArgument { .. } | UnmatchedRightParenthese | InvalidPostfixKeyword(_)
+            | UnmatchedRightBracket | UnterminatedList | MalformedList
+            | FunctionNotInDialect(_) | MalformedDefinition | Redefinition(_) | RecursiveDefinition(_)
+            | UnsupportedPreludeVersion { .. } | DuplicateDefinition { .. } | OnlyDefinitionsAllowed(_) | MalformedImport
+            | ModuleNotFound { .. } | ImportCycle(_) | SourceNotFound(_) | InvalidNativeName(_)
+            | UnterminatedString | InvalidEscapeError(_) | LiteralNotInDialect(_) | InvalidDecimalLiteral | UnrepresentableFinalState { .. }
+            | WrongNumberOfKnownArguments { .. } | UnsupportedSpecialization | UnsupportedSymbolicCommand(_) | UnsupportedSymbolicProgram | MismatchedParameters { .. } | UnsupportedSuperoptimization | UnverifiedFix { .. }
+            | WrongNumberOfArguments { .. } | NonNumeralFinalState { .. } | EmptyStackFinalState { .. } => None,
+        }
+    }
 }
@@ -0,0 +1,241 @@
+use std::fmt;
+
+use super::{
+    config::{
+        PostfixConfig,
+        PostfixExtension,
+        PostfixOverflow,
+        PostfixTyping,
+    },
+    enums::{
+        apply_function,
+        PostfixArithmetic,
+        PostfixCommand,
+        PostfixFunction,
+        PostfixProgram,
+    },
+    equivalence::{
+        check_equivalence_without,
+        EquivalenceBounds,
+        PostfixEquivalence,
+    },
+    errors::PostfixError,
+    specializer::parameter_uses,
+};
+
+
+// code that has no bearing on what a program gives, which a fix takes out
+#[derive(Debug,Clone,PartialEq)]
+pub enum PostfixDeadCode {
+    // commands run one after the other, the first at `location`, that push values only for them to be popped
+    // among themselves and cannot fail on the way
+    COMMANDS { location: Vec<usize>, commands: Vec<PostfixCommand> },
+    // a parameter, counted from 1, whose argument never reaches the result
+    PARAMETER(usize),
+}
+
+impl fmt::Display for PostfixDeadCode {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PostfixDeadCode::COMMANDS { location, commands } => {
+                write!(formatter, "remove `{}` at {:?}", commands.iter().map(|command| command.to_string()).collect::<Vec<_>>().join(" "), location)
+            },
+            PostfixDeadCode::PARAMETER(position) => write!(formatter, "remove parameter {}", position),
+        }
+    }
+}
+
+// a program rewritten without its dead code, along with what was taken out, each removal located in the
+// program as it stands once the ones before it are made, and how it compares with the original
+#[derive(Debug)]
+pub struct PostfixFix {
+    pub removed: Vec<PostfixDeadCode>,
+    // derived from the program as compiled rather than from its source: definitions, imports and the prelude
+    // come inlined, and when shown natives read as `native` and rationals as a/b, which do not parse back
+    pub program: PostfixProgram,
+    // always EQUIVALENT, exhaustively or on samples
+    pub verification: PostfixEquivalence,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+enum Kind {
+    INTEGER,
+    // any other value but a sequence
+    OTHER,
+    SEQUENCE,
+    UNKNOWN,
+}
+
+// a value known to be on the stack, with the integer it is when that is known too
+type Known = (Kind, Option<i32>);
+
+// the top of the stack once `command` has run on `stack`, None when it may fail
+fn run_safely(stack: &[Known], command: &PostfixCommand, config: &PostfixConfig) -> Option<Vec<Known>> {
+    use PostfixArithmetic::*;
+    use PostfixCommand::*;
+    use PostfixFunction::*;
+    let mut stack = stack.to_vec();
+    let length = stack.len();
+    match (command, stack.as_slice()) {
+        (INTEGER(n), _) => stack.push((Kind::INTEGER, Some(*n))),
+        (EXECUTE(_), _) => stack.push((Kind::SEQUENCE, None)),
+        (SPECIAL(POP), [.., _]) => { stack.pop(); },
+        (SPECIAL(SWAP), [.., _, _]) => stack.swap(length - 1, length - 2),
+        (SPECIAL(NGET), [.., (_, Some(index))]) => {
+            let index = *index;
+            stack.pop();
+            match usize::try_from(index) {
+                Ok(index) if index >= 1 && index < length && matches!(stack[length - 1 - index].0, Kind::INTEGER | Kind::OTHER) => stack.push(stack[length - 1 - index]),
+                _ => return None,
+            }
+        },
+        (SPECIAL(SEL), [.., (Kind::INTEGER, _), (then, _), (otherwise, _)]) if config.typing == PostfixTyping::LOOSE => {
+            let kind = match then == otherwise {
+                true => *then,
+                false => Kind::UNKNOWN,
+            };
+            stack.truncate(length - 3);
+            stack.push((kind, None));
+        },
+        (SPECIAL(ARITHMETIC(operator)), [.., (Kind::INTEGER, pre), (Kind::INTEGER, post)]) => {
+            let truth = match config.typing {
+                PostfixTyping::LOOSE => Kind::INTEGER,
+                PostfixTyping::STRICT => Kind::OTHER,
+            };
+            let result = match (operator, pre, post) {
+                (_, Some(pre), Some(post)) => match apply_function(ARITHMETIC(*operator), &[INTEGER(*pre), INTEGER(*post)], config).ok()?.as_slice() {
+                    [INTEGER(n)] => (Kind::INTEGER, Some(*n)),
+                    _ => (Kind::OTHER, None),
+                },
+                (EQ | NE | LT | LE | GT | GE, _, _) => (truth, None),
+                (ADD | SUB | MUL, _, _) if config.overflow == PostfixOverflow::WRAPPING => (Kind::INTEGER, None),
+                (DIV | REM, _, Some(post)) if *post != 0 && *post != -1 && !config.extensions.contains(&PostfixExtension::RATIONAL) => (Kind::INTEGER, None),
+                (_, _, _) => return None,
+            };
+            stack.truncate(length - 2);
+            stack.push(result);
+        },
+        (SPECIAL(_), _) => return None,
+        (_, _) => stack.push((Kind::OTHER, None)),
+    };
+    Some(stack)
+}
+
+// the top of the stack once `command` has run on `stack`, provided it does not fail
+fn run_known(stack: &[Known], command: &PostfixCommand, config: &PostfixConfig) -> Vec<Known> {
+    match (run_safely(stack, command, config), command) {
+        (Some(stack), _) => stack,
+        (None, PostfixCommand::SPECIAL(function)) => match function.effect() {
+            Some((inputs, outputs)) => {
+                let mut stack = stack[..stack.len().saturating_sub(inputs)].to_vec();
+                stack.extend(std::iter::repeat_n((Kind::UNKNOWN, None), outputs));
+                stack
+            },
+            None => Vec::new(),
+        },
+        (None, _) => Vec::new(),
+    }
+}
+
+// how many of the commands, from the first one, leave `stack` as it was without failing or touching it
+fn dead_segment(commands: &[PostfixCommand], stack: &[Known], config: &PostfixConfig) -> usize {
+    let base = stack.len();
+    let mut current = stack.to_vec();
+    let mut length = 0;
+    for (offset, command) in commands.iter().enumerate() {
+        let inputs = match command {
+            PostfixCommand::SPECIAL(function) => function.effect().map_or(usize::MAX, |(inputs, _)| inputs),
+            _ => 0,
+        };
+        match (current.len() - base >= inputs, run_safely(&current, command, config)) {
+            (true, Some(next)) => current = next,
+            (_, _) => break,
+        };
+        if current.len() == base {
+            length = offset + 1;
+        }
+    }
+    length
+}
+
+fn located(location: &[usize], index: usize) -> Vec<usize> {
+    location.iter().copied().chain(std::iter::once(index)).collect()
+}
+
+// the commands without their dead segments, those of sequences included
+fn remove_segments(commands: &[PostfixCommand], stack: Vec<Known>, location: &[usize], config: &PostfixConfig, removed: &mut Vec<PostfixDeadCode>) -> Vec<PostfixCommand> {
+    let mut kept = Vec::new();
+    let mut stack = stack;
+    let mut index = 0;
+    while index < commands.len() {
+        match dead_segment(&commands[index..], &stack, config) {
+            0 => {
+                let command = match &commands[index] {
+                    PostfixCommand::EXECUTE(body) => PostfixCommand::EXECUTE(remove_segments(body, Vec::new(), &located(location, index), config, removed)),
+                    command => command.to_owned(),
+                };
+                stack = run_known(&stack, &command, config);
+                kept.push(command);
+                index += 1;
+            },
+            length => {
+                removed.push(PostfixDeadCode::COMMANDS { location: located(location, index), commands: commands[index..index + length].to_vec() });
+                index += length;
+            },
+        }
+    }
+    kept
+}
+
+// how to do without a parameter: the pop dropping its argument if any, and the nget indices to lower by one
+// as they reach past it. None unless the argument is never read.
+fn parameter_removal(program: &PostfixProgram, parameter: usize) -> Option<(Option<usize>, Vec<usize>)> {
+    parameter_uses(program, parameter).filter(|uses| uses.reads.is_empty()).map(|uses| (uses.pop, uses.lowered))
+}
+
+// the program without any dead code found, along with what was taken out
+fn remove_dead_code(program: &PostfixProgram, config: &PostfixConfig) -> (PostfixProgram, Vec<PostfixDeadCode>) {
+    let mut current = PostfixProgram::new(program.paramsize, program.variadic, program.commands.to_owned());
+    let mut positions: Vec<usize> = (1..=program.paramsize as usize).collect();
+    let mut removed = Vec::new();
+    // one removal may leave more to be found, as a copy dropped leaves its parameter unused
+    loop {
+        let before = removed.len();
+        let arguments = vec![(Kind::INTEGER, None); current.paramsize as usize + current.variadic as usize];
+        current.commands = remove_segments(&current.commands, arguments, &[], config, &mut removed);
+        let removal = (1..=current.paramsize as usize).find_map(|parameter| parameter_removal(&current, parameter).map(|removal| (parameter, removal)));
+        if let Some((parameter, (pop, lowered))) = removal {
+            current.commands = current.commands.iter().enumerate().filter(|(index, _)| Some(*index) != pop).map(|(index, command)| match command {
+                PostfixCommand::INTEGER(reach) if lowered.contains(&index) => PostfixCommand::INTEGER(reach - 1),
+                command => command.to_owned(),
+            }).collect();
+            current.paramsize -= 1;
+            removed.push(PostfixDeadCode::PARAMETER(positions.remove(parameter - 1)));
+        }
+        if removed.len() == before {
+            return (current, removed);
+        }
+    }
+}
+
+// commands whose results are always popped without bearing on the result, and parameters never reaching it
+pub fn find_dead_code(program: &PostfixProgram, config: &PostfixConfig) -> Vec<PostfixDeadCode> {
+    remove_dead_code(program, config).1
+}
+
+// the program without its dead code, checked against the original on the arguments within `bounds`;
+// None when there is nothing to remove, and UnverifiedFix when the check finds arguments telling them apart
+pub fn fix_dead_code(program: &PostfixProgram, bounds: &EquivalenceBounds, config: &PostfixConfig) -> Result<Option<PostfixFix>, PostfixError> {
+    let (fixed, removed) = remove_dead_code(program, config);
+    if removed.is_empty() {
+        return Ok(None);
+    }
+    let parameters: Vec<usize> = removed.iter().filter_map(|removal| match removal {
+        PostfixDeadCode::PARAMETER(position) => Some(*position),
+        PostfixDeadCode::COMMANDS { .. } => None,
+    }).collect();
+    match check_equivalence_without(program, &fixed, &parameters, bounds, config)? {
+        PostfixEquivalence::COUNTEREXAMPLE { arguments, .. } => Err(PostfixError::UnverifiedFix { arguments }),
+        verification => Ok(Some(PostfixFix { removed, program: fixed, verification })),
+    }
+}
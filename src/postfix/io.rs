@@ -0,0 +1,42 @@
+use std::io::{
+    self,
+    BufRead,
+    Write,
+};
+
+
+// effects of the io extension go through the host instead of touching stdin/stdout directly
+pub trait HostIo {
+    // next integer of the input, None once the input is exhausted
+    fn read(&mut self) -> Option<i32>;
+    fn write(&mut self, text: &str);
+}
+
+// one integer per line on stdin, output straight to stdout
+pub struct StandardIo;
+
+impl HostIo for StandardIo {
+    fn read(&mut self) -> Option<i32> {
+        let mut line = String::new();
+        match io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => line.trim().parse().ok(),
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        print! ("{}", text);
+        io::stdout().flush().unwrap_or(());
+    }
+}
+
+// no input at all and output dropped, for runs whose result alone matters
+pub struct SilentIo;
+
+impl HostIo for SilentIo {
+    fn read(&mut self) -> Option<i32> {
+        None
+    }
+
+    fn write(&mut self, _text: &str) {}
+}
@@ -1,3 +1,7 @@
+use std::fmt;
+
+use num::rational::Rational64;
+
 use super::{
     enums::{ 
         PostfixKeyword, 
@@ -24,13 +28,21 @@ pub enum NameCharacter {
 pub enum BreakingCharacter {
     LEFT,
     RIGHT,
+    LEFTSQUARE,
+    RIGHTSQUARE,
+    // arity markers of the header, `(postfix * ...)` and `(postfix 2+ ...)`
+    STAR,
+    PLUS,
     SPACE,
+    QUOTE,
 }
 
 #[derive(Debug)]
 pub enum Character {
     BREAK(BreakingCharacter),
     NAME(NameCharacter),
+    // decimal point, only valid within a number
+    POINT,
 }
 
 // routing type: control creation of all its managed types (BreakingCharacter, NameCharacter)
@@ -40,9 +52,15 @@ impl TryFrom<char> for Character {
         match c {
             '(' => Ok(Character::BREAK(BreakingCharacter::LEFT)),
             ')' => Ok(Character::BREAK(BreakingCharacter::RIGHT)),
-            ' ' => Ok(Character::BREAK(BreakingCharacter::SPACE)),
+            '[' => Ok(Character::BREAK(BreakingCharacter::LEFTSQUARE)),
+            ']' => Ok(Character::BREAK(BreakingCharacter::RIGHTSQUARE)),
+            '*' => Ok(Character::BREAK(BreakingCharacter::STAR)),
+            '+' => Ok(Character::BREAK(BreakingCharacter::PLUS)),
+            ' ' | '\t' | '\n' | '\r' => Ok(Character::BREAK(BreakingCharacter::SPACE)),
+            '"' => Ok(Character::BREAK(BreakingCharacter::QUOTE)),
+            '.' => Ok(Character::POINT),
             'a'..='z' | 'A'..='Z' => Ok(Character::NAME(NameCharacter::ALPHABET(Alphabet(c)))),
-            '0'..='9' => Ok(Character::NAME(NameCharacter::DIGIT(Digit(c as u8 - '0' as u8)))),
+            '0'..='9' => Ok(Character::NAME(NameCharacter::DIGIT(Digit(c as u8 - b'0')))),
             c => Err(PostfixError::InvalidCharacterError(c))
         }
     }
@@ -55,12 +73,12 @@ pub struct FunctionBuilder {
 }
 
 impl FunctionBuilder {
-    pub fn to_string(self) -> String {
+    pub fn into_string(self) -> String {
         use NameCharacter::*;
         let Alphabet(head) = self.head;
         let tail: String = self.tail.into_iter().map(|nc| match nc {
             ALPHABET(Alphabet(c)) => c,
-            DIGIT(Digit(d)) => d.into()
+            DIGIT(Digit(d)) => (b'0' + d) as char,
         }).collect();
         let mut final_string = head.to_string();
         final_string.push_str(&tail);
@@ -68,10 +86,26 @@ impl FunctionBuilder {
     }
 }
 
+// content of a string literal read so far; `escaped` after a backslash
+#[derive(Debug,Default)]
+pub struct StringBuilder {
+    pub text: String,
+    pub escaped: bool,
+}
+
+// digits of a decimal literal read so far, `scale` of them after the point
+#[derive(Debug)]
+pub struct DecimalBuilder {
+    pub digits: i64,
+    pub scale: u32,
+}
+
 #[derive(Debug)]
 pub enum TokenBuilder {
     TOBENUMBER(i32),
+    TOBEDECIMAL(DecimalBuilder),
     TOBEWORD(FunctionBuilder),
+    TOBESTRING(StringBuilder),
 }
 
 impl TryFrom<FunctionBuilder> for PostfixKeyword {
@@ -83,6 +117,11 @@ impl TryFrom<FunctionBuilder> for PostfixKeyword {
         use PostfixArithmetic::*;
         match (tbf.head, tbf.tail.as_slice()) {
             (Alphabet('e'), [ALPHABET(Alphabet('q'))]) => Ok(FUNCTION(ARITHMETIC(EQ))),
+            (Alphabet('a'), [ALPHABET(Alphabet('t'))]) => Ok(FUNCTION(AT)),
+            (Alphabet('n'), [ALPHABET(Alphabet('e'))]) => Ok(FUNCTION(ARITHMETIC(NE))),
+            (Alphabet('l'), [ALPHABET(Alphabet('e'))]) => Ok(FUNCTION(ARITHMETIC(LE))),
+            (Alphabet('g'), [ALPHABET(Alphabet('e'))]) => Ok(FUNCTION(ARITHMETIC(GE))),
+            (Alphabet('d'), [ALPHABET(Alphabet('e')), ALPHABET(Alphabet('f'))]) => Ok(DEF),
             (Alphabet('l'), [ALPHABET(Alphabet('t'))]) => Ok(FUNCTION(ARITHMETIC(LT))),
             (Alphabet('g'), [ALPHABET(Alphabet('t'))]) => Ok(FUNCTION(ARITHMETIC(GT))),
 
@@ -91,30 +130,100 @@ impl TryFrom<FunctionBuilder> for PostfixKeyword {
             (Alphabet('m'), [ALPHABET(Alphabet('u')), ALPHABET(Alphabet('l'))]) => Ok(FUNCTION(ARITHMETIC(MUL))),
             (Alphabet('r'), [ALPHABET(Alphabet('e')), ALPHABET(Alphabet('m'))]) => Ok(FUNCTION(ARITHMETIC(REM))),
             (Alphabet('s'), [ALPHABET(Alphabet('u')), ALPHABET(Alphabet('b'))]) => Ok(FUNCTION(ARITHMETIC(SUB))),
+            (Alphabet('b'), [ALPHABET(Alphabet('o')), ALPHABET(Alphabet('r'))]) => Ok(FUNCTION(ARITHMETIC(BOR))),
+            (Alphabet('m'), [ALPHABET(Alphabet('a')), ALPHABET(Alphabet('x'))]) => Ok(FUNCTION(ARITHMETIC(MAX))),
+            (Alphabet('m'), [ALPHABET(Alphabet('i')), ALPHABET(Alphabet('n'))]) => Ok(FUNCTION(ARITHMETIC(MIN))),
+            (Alphabet('p'), [ALPHABET(Alphabet('o')), ALPHABET(Alphabet('w'))]) => Ok(FUNCTION(ARITHMETIC(POW))),
+            (Alphabet('m'), [ALPHABET(Alphabet('a')), ALPHABET(Alphabet('p'))]) => Ok(FUNCTION(MAP)),
+            (Alphabet('s'), [ALPHABET(Alphabet('h')), ALPHABET(Alphabet('l'))]) => Ok(FUNCTION(ARITHMETIC(SHL))),
+            (Alphabet('s'), [ALPHABET(Alphabet('h')), ALPHABET(Alphabet('r'))]) => Ok(FUNCTION(ARITHMETIC(SHR))),
+            (Alphabet('a'), [ALPHABET(Alphabet('b')), ALPHABET(Alphabet('s'))]) => Ok(FUNCTION(ABS)),
+            (Alphabet('n'), [ALPHABET(Alphabet('e')), ALPHABET(Alphabet('g'))]) => Ok(FUNCTION(NEG)),
 
+            (Alphabet('g'), [ALPHABET(Alphabet('e')), ALPHABET(Alphabet('t'))]) => Ok(FUNCTION(GET)),
+            (Alphabet('t'), [ALPHABET(Alphabet('r')), ALPHABET(Alphabet('y'))]) => Ok(FUNCTION(TRY)),
+            (Alphabet('i'), [ALPHABET(Alphabet('n')), ALPHABET(Alphabet('t'))]) => Ok(FUNCTION(INT)),
+            (Alphabet('l'), [ALPHABET(Alphabet('e')), ALPHABET(Alphabet('n'))]) => Ok(FUNCTION(LEN)),
             (Alphabet('p'), [ALPHABET(Alphabet('o')), ALPHABET(Alphabet('p'))]) => Ok(FUNCTION(POP)),
+            (Alphabet('p'), [ALPHABET(Alphabet('u')), ALPHABET(Alphabet('t'))]) => Ok(FUNCTION(PUT)),
             (Alphabet('s'), [ALPHABET(Alphabet('e')), ALPHABET(Alphabet('l'))]) => Ok(FUNCTION(SEL)),
+            (Alphabet('s'), [ALPHABET(Alphabet('t')), ALPHABET(Alphabet('r'))]) => Ok(FUNCTION(STR)),
+
+            (Alphabet('t'), [ALPHABET(Alphabet('r')), ALPHABET(Alphabet('u')), ALPHABET(Alphabet('e'))]) => Ok(BOOLEAN(true)),
 
+            (Alphabet('e'), [ALPHABET(Alphabet('m')), ALPHABET(Alphabet('i')), ALPHABET(Alphabet('t'))]) => Ok(FUNCTION(EMIT)),
+            (Alphabet('b'), [ALPHABET(Alphabet('a')), ALPHABET(Alphabet('n')), ALPHABET(Alphabet('d'))]) => Ok(FUNCTION(ARITHMETIC(BAND))),
+            (Alphabet('b'), [ALPHABET(Alphabet('x')), ALPHABET(Alphabet('o')), ALPHABET(Alphabet('r'))]) => Ok(FUNCTION(ARITHMETIC(BXOR))),
             (Alphabet('e'), [ALPHABET(Alphabet('x')), ALPHABET(Alphabet('e')), ALPHABET(Alphabet('c'))]) => Ok(FUNCTION(EXEC)),
             (Alphabet('n'), [ALPHABET(Alphabet('g')), ALPHABET(Alphabet('e')), ALPHABET(Alphabet('t'))]) => Ok(FUNCTION(NGET)),
+            (Alphabet('r'), [ALPHABET(Alphabet('e')), ALPHABET(Alphabet('a')), ALPHABET(Alphabet('d'))]) => Ok(FUNCTION(READ)),
+            (Alphabet('f'), [ALPHABET(Alphabet('o')), ALPHABET(Alphabet('l')), ALPHABET(Alphabet('d'))]) => Ok(FUNCTION(FOLD)),
+            (Alphabet('p'), [ALPHABET(Alphabet('u')), ALPHABET(Alphabet('s')), ALPHABET(Alphabet('h'))]) => Ok(FUNCTION(PUSH)),
             (Alphabet('s'), [ALPHABET(Alphabet('w')), ALPHABET(Alphabet('a')), ALPHABET(Alphabet('p'))]) => Ok(FUNCTION(SWAP)),
             
+            (Alphabet('c'), [
+                ALPHABET(Alphabet('o')), ALPHABET(Alphabet('n')), ALPHABET(Alphabet('c')), ALPHABET(Alphabet('a')), ALPHABET(Alphabet('t'))
+            ]) => Ok(FUNCTION(CONCAT)),
+
+            (Alphabet('f'), [
+                ALPHABET(Alphabet('a')), ALPHABET(Alphabet('l')), ALPHABET(Alphabet('s')), ALPHABET(Alphabet('e'))
+            ]) => Ok(BOOLEAN(false)),
+
+            (Alphabet('t'), [
+                ALPHABET(Alphabet('h')), ALPHABET(Alphabet('r')), ALPHABET(Alphabet('o')), ALPHABET(Alphabet('w'))
+            ]) => Ok(FUNCTION(THROW)),
+
+            (Alphabet('p'), [
+                ALPHABET(Alphabet('r')), ALPHABET(Alphabet('i')), ALPHABET(Alphabet('n')), ALPHABET(Alphabet('t'))
+            ]) => Ok(FUNCTION(PRINT)),
+
+            (Alphabet('r'), [
+                ALPHABET(Alphabet('e')), ALPHABET(Alphabet('p')), ALPHABET(Alphabet('e')), ALPHABET(Alphabet('a')), ALPHABET(Alphabet('t'))
+            ]) => Ok(FUNCTION(REPEAT)),
+
+            (Alphabet('i'), [
+                ALPHABET(Alphabet('m')), ALPHABET(Alphabet('p')), ALPHABET(Alphabet('o')), ALPHABET(Alphabet('r')), ALPHABET(Alphabet('t'))
+            ]) => Ok(IMPORT),
+
             (Alphabet('p'), [
                 ALPHABET(Alphabet('o')), ALPHABET(Alphabet('s')), ALPHABET(Alphabet('t')), 
                 ALPHABET(Alphabet('f')), ALPHABET(Alphabet('i')), ALPHABET(Alphabet('x'))
             ]) => Ok(HEAD),
 
-            (head, tail_slice) => Err(PostfixError::InvalidFunctionNameError(FunctionBuilder{ head, tail: tail_slice.into()}.to_string()))
+            (head, tail_slice) => Err(PostfixError::InvalidFunctionNameError(FunctionBuilder{ head, tail: tail_slice.into()}.into_string()))
         }
     }
 }
 
-#[derive(Debug,Clone,Copy)]
+// where a token or a comment starts in the source, counted from line 1 and column 1
+#[derive(Debug,Copy,Clone,Default,PartialEq,Eq,PartialOrd,Ord)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}:{}", self.line, self.column)
+    }
+}
+
+// a token or a comment along with where it starts
+pub type Located<T> = (T, Position);
+
+#[derive(Debug,Clone)]
 pub enum Token {
     LEFT,
     RIGHT,
+    LEFTSQUARE,
+    RIGHTSQUARE,
+    STAR,
+    PLUS,
     NUMBER(i32),
+    DECIMAL(Rational64),
     KEYWORD(PostfixKeyword),
+    NAME(String),
+    STRING(String),
 }
 
 impl TryFrom<TokenBuilder> for Token {
@@ -123,13 +232,25 @@ impl TryFrom<TokenBuilder> for Token {
         use TokenBuilder::*;
         Ok(match tbt {
             TOBENUMBER(d) => Self::NUMBER(d),
-            TOBEWORD(w) => Self::KEYWORD(w.try_into()?)
+            // a point has to be followed by at least one digit
+            TOBEDECIMAL(DecimalBuilder { scale: 0, .. }) => return Err(PostfixError::InvalidDecimalLiteral),
+            TOBEDECIMAL(DecimalBuilder { digits, scale }) => match 10i64.checked_pow(scale) {
+                Some(denominator) => Self::DECIMAL(Rational64::new(digits, denominator)),
+                None => return Err(PostfixError::InvalidDecimalLiteral),
+            },
+            TOBEWORD(w) => match w.try_into() {
+                Ok(keyword) => Self::KEYWORD(keyword),
+                // not a keyword: left to the parser to resolve against the definitions
+                Err(PostfixError::InvalidFunctionNameError(name)) => Self::NAME(name),
+                Err(error) => return Err(error),
+            },
+            TOBESTRING(_) => return Err(PostfixError::UnterminatedString),
         })
     }
 }
 
 impl TokenBuilder {
-    fn consume(self: Self, character: NameCharacter) -> Result<Self, PostfixError> {
+    fn consume(self, character: NameCharacter) -> Result<Self, PostfixError> {
         use TokenBuilder::*;
         use NameCharacter::*;
         match self {
@@ -137,7 +258,21 @@ impl TokenBuilder {
                 ALPHABET(a) => Err(PostfixError::NumberLiteralFollowedByAlphabetError(n, a)),
                 DIGIT(Digit(d)) => Ok(TOBENUMBER(n * 10 + d as i32)),
             },
+            TOBEDECIMAL(DecimalBuilder { digits, scale }) => match character {
+                ALPHABET(_) => Err(PostfixError::InvalidDecimalLiteral),
+                DIGIT(Digit(d)) => match digits.checked_mul(10).and_then(|digits| digits.checked_add(d as i64)) {
+                    Some(digits) => Ok(TOBEDECIMAL(DecimalBuilder { digits, scale: scale + 1 })),
+                    None => Err(PostfixError::InvalidDecimalLiteral),
+                },
+            },
             TOBEWORD(mut tbf) => { tbf.tail.push(character); Ok(TOBEWORD(tbf)) },
+            TOBESTRING(mut tbs) => {
+                tbs.text.push(match character {
+                    ALPHABET(Alphabet(c)) => c,
+                    DIGIT(Digit(d)) => (b'0' + d) as char,
+                });
+                Ok(TOBESTRING(tbs))
+            },
         }
     }
 }
@@ -159,35 +294,121 @@ fn parse_one_character_with_tobetoken(tobetoken: Option<TokenBuilder>, character
 -> Result<(Option<TokenBuilder>, Vec<Token>), PostfixError> {
     use Character::*;
     use BreakingCharacter::*;
-    let result = Ok(match character {
-        BREAK(character) => (None, vec! [
-            match tobetoken {
-                Some(t) => Some(t.try_into()?),
-                None => None,
-            },
+    Ok(match character {
+        BREAK(character) => (
             match character {
-                LEFT => Some(Token::LEFT),
-                RIGHT => Some(Token::RIGHT),
-                SPACE => None,
-            }
-        ].into_iter().flatten().collect()),
+                QUOTE => Some(TokenBuilder::TOBESTRING(StringBuilder::default())),
+                LEFT | RIGHT | LEFTSQUARE | RIGHTSQUARE | STAR | PLUS | SPACE => None,
+            },
+            vec! [
+                match tobetoken {
+                    Some(t) => Some(t.try_into()?),
+                    None => None,
+                },
+                match character {
+                    LEFT => Some(Token::LEFT),
+                    RIGHT => Some(Token::RIGHT),
+                    LEFTSQUARE => Some(Token::LEFTSQUARE),
+                    RIGHTSQUARE => Some(Token::RIGHTSQUARE),
+                    STAR => Some(Token::STAR),
+                    PLUS => Some(Token::PLUS),
+                    SPACE | QUOTE => None,
+                }
+            ].into_iter().flatten().collect()
+        ),
         NAME(character) => (Some(parse_name_character_to_tobetoken(
             tobetoken, character
         )?), vec! []),
+        POINT => (Some(match tobetoken {
+            Some(TokenBuilder::TOBENUMBER(n)) => TokenBuilder::TOBEDECIMAL(DecimalBuilder { digits: n as i64, scale: 0 }),
+            Some(TokenBuilder::TOBEDECIMAL(_)) => return Err(PostfixError::InvalidDecimalLiteral),
+            Some(TokenBuilder::TOBEWORD(_) | TokenBuilder::TOBESTRING(_)) | None => return Err(PostfixError::InvalidCharacterError('.')),
+        }), vec! []),
+
+    })
+}
+
+// inside a string literal every character is taken as is, up to the closing quote
+fn parse_string_character(builder: StringBuilder, character: char)
+-> Result<(Option<TokenBuilder>, Vec<Token>), PostfixError> {
+    use TokenBuilder::*;
+    let StringBuilder { mut text, escaped } = builder;
+    Ok(match (escaped, character) {
+        (false, '"') => (None, vec! [Token::STRING(text)]),
+        (false, '\\') => (Some(TOBESTRING(StringBuilder { text, escaped: true })), vec! []),
+        (false, c) => { text.push(c); (Some(TOBESTRING(StringBuilder { text, escaped: false })), vec! []) },
+        (true, c) => {
+            text.push(match c {
+                'n' => '\n',
+                't' => '\t',
+                '"' | '\\' => c,
+                c => return Err(PostfixError::InvalidEscapeError(c)),
+            });
+            (Some(TOBESTRING(StringBuilder { text, escaped: false })), vec! [])
+        },
+    })
+}
 
-    });
-    result
+pub fn parse_code_to_tokens(code: &str) -> Result<Vec<Located<Token>>, PostfixError> {
+    parse_code_to_tokens_and_comments(code).map(|(tokens, _)| tokens)
 }
 
-pub fn parse_code_to_tokens(code: &str) -> Result<Vec<Token>, PostfixError> {
+// the tokens of some code along with the text of each comment, which runs from a semicolon to the end of its line
+pub type TokensAndComments = (Vec<Located<Token>>, Vec<Located<String>>);
+
+pub fn parse_code_to_tokens_and_comments(code: &str) -> Result<TokensAndComments, PostfixError> {
     let mut tokens = Vec::new();
-    code.chars().map(|c| c.try_into()).fold(
-        Ok(None),
-        | tobetoken: Result<Option<TokenBuilder>, PostfixError>, character | {
-            let (new_tobetoken, mut new_tokens) 
-                = parse_one_character_with_tobetoken(tobetoken?, character?)?;
-            tokens.append(&mut new_tokens);
-            Ok(new_tobetoken)
+    let mut comments = Vec::new();
+    let mut comment: Option<Located<String>> = None;
+    let mut current = Position { line: 1, column: 1 };
+    // where the pending token started
+    let mut start = current;
+    // the end of the code breaks a pending token just like a space does
+    let tobetoken = code.chars().chain(std::iter::once(' ')).try_fold(
+        None,
+        | tobetoken: Option<TokenBuilder>, character | {
+            let position = current;
+            current = match character {
+                '\n' => Position { line: current.line + 1, column: 1 },
+                _ => Position { column: current.column + 1, ..current },
+            };
+            if let Some((text, at)) = comment.as_mut() {
+                match character {
+                    '\n' => { comments.push((text.trim().to_owned(), *at)); comment = None },
+                    character => text.push(character),
+                };
+                return Ok(tobetoken);
+            }
+            let pending = tobetoken.is_some();
+            let (new_tobetoken, new_tokens) = match (tobetoken, character) {
+                (Some(TokenBuilder::TOBESTRING(builder)), character) => parse_string_character(builder, character)?,
+                // line comments are a change to the language: the book's Postfix has none, and a semicolon used to
+                // fail with InvalidCharacterError; it now starts a comment and breaks a pending token like a space does
+                (tobetoken, ';') => {
+                    comment = Some((String::new(), position));
+                    parse_one_character_with_tobetoken(tobetoken, Character::BREAK(BreakingCharacter::SPACE))?
+                },
+                (tobetoken, character) => parse_one_character_with_tobetoken(tobetoken, character.try_into()?)?,
+            };
+            // a pending token comes out before the one the character itself makes
+            let emitted = !new_tokens.is_empty();
+            let positions = match pending {
+                true => [start, position],
+                false => [position, position],
+            };
+            tokens.extend(new_tokens.into_iter().zip(positions));
+            // the character starts a token of its own, unless it carries on the pending one
+            if new_tobetoken.is_some() && (!pending || emitted) {
+                start = position;
+            }
+            Ok::<_, PostfixError>(new_tobetoken)
         }
-    ).and(Ok(tokens))
-}
\ No newline at end of file
+    )?;
+    if let Some((text, at)) = comment {
+        comments.push((text.trim().to_owned(), at));
+    }
+    match tobetoken {
+        Some(TokenBuilder::TOBESTRING(_)) => Err(PostfixError::UnterminatedString),
+        Some(_) | None => Ok((tokens, comments)),
+    }
+}
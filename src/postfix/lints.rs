@@ -0,0 +1,404 @@
+use std::{
+    fmt,
+    path::Path,
+};
+
+use super::{
+    config::PostfixConfig,
+    enums::{
+        PostfixArithmetic,
+        PostfixCommand,
+        PostfixFunction,
+        PostfixKeyword,
+        PostfixProgram,
+    },
+    errors::PostfixError,
+    lexer::{
+        parse_code_to_tokens_and_comments,
+        Located,
+        Position,
+        Token,
+    },
+    modules::{
+        FileSystemLoader,
+        ModuleLoader,
+    },
+    programs::parse_tokens_to_program_with_loader,
+};
+
+
+#[derive(Debug,Copy,Clone,PartialEq,PartialOrd)]
+pub enum PostfixSeverity {
+    INFO,
+    WARNING,
+    ERROR,
+}
+
+impl PostfixSeverity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PostfixSeverity::INFO => "info",
+            PostfixSeverity::WARNING => "warning",
+            PostfixSeverity::ERROR => "error",
+        }
+    }
+}
+
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum PostfixLintKind {
+    // `1 (a) (b) sel`, where `(b)` is never selected
+    CONSTANTCONDITION,
+    // `(a) pop`, a sequence dropped without ever being run
+    UNUSEDSEQUENCE,
+    // `swap swap`, `1 nget pop` or `0 add`, which leave the stack as it was
+    NOOPPAIR,
+    // an argument dropped without being read
+    UNUSEDPARAMETER,
+    // `lt 1 add`, a truth value taken as a number
+    COMPARISONARITHMETIC,
+    // a program whose runs all end with a sequence on top, failing with NonNumeralFinalState
+    SEQUENCERESULT,
+}
+
+impl PostfixLintKind {
+    pub const ALL: [PostfixLintKind; 6] = [
+        PostfixLintKind::CONSTANTCONDITION,
+        PostfixLintKind::UNUSEDSEQUENCE,
+        PostfixLintKind::NOOPPAIR,
+        PostfixLintKind::UNUSEDPARAMETER,
+        PostfixLintKind::COMPARISONARITHMETIC,
+        PostfixLintKind::SEQUENCERESULT,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        use PostfixLintKind::*;
+        match self {
+            CONSTANTCONDITION => "L001",
+            UNUSEDSEQUENCE => "L002",
+            NOOPPAIR => "L003",
+            UNUSEDPARAMETER => "L004",
+            COMPARISONARITHMETIC => "L005",
+            SEQUENCERESULT => "L006",
+        }
+    }
+
+    // how the lint is named in an `allow(...)` comment, along with its id
+    pub fn name(&self) -> &'static str {
+        use PostfixLintKind::*;
+        match self {
+            CONSTANTCONDITION => "constant-condition",
+            UNUSEDSEQUENCE => "unused-sequence",
+            NOOPPAIR => "no-op-pair",
+            UNUSEDPARAMETER => "unused-parameter",
+            COMPARISONARITHMETIC => "comparison-arithmetic",
+            SEQUENCERESULT => "sequence-result",
+        }
+    }
+
+    pub fn severity(&self) -> PostfixSeverity {
+        use PostfixLintKind::*;
+        match self {
+            NOOPPAIR => PostfixSeverity::INFO,
+            CONSTANTCONDITION | UNUSEDSEQUENCE | UNUSEDPARAMETER | COMPARISONARITHMETIC => PostfixSeverity::WARNING,
+            SEQUENCERESULT => PostfixSeverity::ERROR,
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct PostfixLint {
+    pub kind: PostfixLintKind,
+    // position of the command in question through the sequences holding it, empty for the whole program
+    pub location: Vec<usize>,
+    // where the command is written in the source, None for the whole program or a program not read from source
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl fmt::Display for PostfixLint {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}[{}] {}", self.kind.severity().name(), self.kind.id(), self.kind.name())?;
+        match (self.position, self.location.is_empty()) {
+            (Some(position), _) => write!(formatter, " at {}: {}", position, self.message),
+            (None, true) => write!(formatter, ": {}", self.message),
+            (None, false) => write!(formatter, " at {:?}: {}", self.location, self.message),
+        }
+    }
+}
+
+fn located(location: &[usize], index: usize) -> Vec<usize> {
+    location.iter().copied().chain(std::iter::once(index)).collect()
+}
+
+// lints seen in the commands as written, in every sequence however it is run
+fn written_lints(commands: &[PostfixCommand], location: &[usize], lints: &mut Vec<PostfixLint>) {
+    use PostfixArithmetic::*;
+    use PostfixCommand::*;
+    use PostfixFunction::*;
+    use PostfixLintKind::*;
+    let lint = |kind, index, message: String| PostfixLint { kind, location: located(location, index), position: None, message };
+    commands.iter().enumerate().for_each(|(index, command)| {
+        match &commands[index..] {
+            [SPECIAL(SWAP), SPECIAL(SWAP), ..] => lints.push(lint(NOOPPAIR, index, "`swap swap` leaves the stack as it was".to_string())),
+            [INTEGER(1), SPECIAL(NGET), SPECIAL(POP), ..] => lints.push(lint(NOOPPAIR, index, "`1 nget pop` leaves the stack as it was".to_string())),
+            [INTEGER(0), SPECIAL(ARITHMETIC(ADD | SUB)), ..] | [INTEGER(1), SPECIAL(ARITHMETIC(MUL | DIV)), ..] => {
+                lints.push(lint(NOOPPAIR, index, format!("`{} {}` leaves an integer as it was", command, commands[index + 1])))
+            },
+            [EXECUTE(_), SPECIAL(POP), ..] => lints.push(lint(UNUSEDSEQUENCE, index, format!("`{}` is dropped without being run", command))),
+            [pushed, SPECIAL(POP), ..] if !matches!(pushed, SPECIAL(_)) => lints.push(lint(NOOPPAIR, index, format!("`{} pop` leaves the stack as it was", pushed))),
+            _ => (),
+        };
+        match &commands[index..] {
+            [condition @ (INTEGER(_) | BOOLEAN(_)), then, otherwise, SPECIAL(SEL), ..] if !matches!(then, SPECIAL(_)) && !matches!(otherwise, SPECIAL(_)) => {
+                let (holds, unreachable) = match condition {
+                    INTEGER(0) | BOOLEAN(false) => (false, then),
+                    _ => (true, otherwise),
+                };
+                lints.push(lint(CONSTANTCONDITION, index + 3, format!("the condition is always {}, `{}` is never selected", holds, unreachable)))
+            },
+            _ => (),
+        };
+        if let EXECUTE(body) = command {
+            written_lints(body, &located(location, index), lints);
+        }
+    });
+}
+
+// what a value on the stack may be: one of the given arguments, counted from 1, one of the given sequences
+// pushed, or anything else when `data`
+#[derive(Debug,Clone,Default)]
+struct Tracked {
+    arguments: Vec<usize>,
+    sequences: Vec<(Vec<usize>, Vec<PostfixCommand>)>,
+    data: bool,
+    // produced by a comparison
+    truth: bool,
+    // pushed as a literal
+    literal: Option<PostfixCommand>,
+}
+
+impl Tracked {
+    // the value a sel with an unknown condition gives, either of the two
+    fn either(mut self, other: Tracked) -> Tracked {
+        self.arguments.extend(other.arguments);
+        self.sequences.extend(other.sequences);
+        Tracked {
+            data: self.data || other.data,
+            truth: self.truth || other.truth,
+            literal: match self.literal == other.literal { true => self.literal, false => None },
+            ..self
+        }
+    }
+
+    fn sequence_only(&self) -> bool {
+        !self.data && self.arguments.is_empty() && !self.sequences.is_empty()
+    }
+}
+
+// one run followed through the program, `finished` when it got to the end
+#[derive(Debug,Clone,Default)]
+struct Flow {
+    stack: Vec<Tracked>,
+    pending: Vec<(PostfixCommand, Vec<usize>)>,
+    pushed: Vec<Vec<usize>>,
+    used: Vec<Vec<usize>>,
+    read: Vec<usize>,
+    mixed: Vec<Vec<usize>>,
+    finished: bool,
+}
+
+// runs followed at most, beyond which nothing is concluded from them
+const FLOWS: usize = 256;
+
+impl Flow {
+    fn take(&mut self, value: &Tracked) {
+        self.read.extend(value.arguments.iter().copied());
+        self.used.extend(value.sequences.iter().map(|(location, _)| location.to_owned()));
+    }
+
+    // each run the flow may go on to, with its end pushed to `flows`; false once there are too many
+    fn follow(mut self, flows: &mut Vec<Flow>) -> bool {
+        use PostfixArithmetic::*;
+        use PostfixCommand::*;
+        use PostfixFunction::*;
+        loop {
+            let Some((command, location)) = self.pending.pop() else {
+                self.finished = true;
+                break;
+            };
+            match command {
+                EXECUTE(body) => {
+                    self.pushed.push(location.clone());
+                    self.stack.push(Tracked { sequences: vec![(location, body)], ..Tracked::default() });
+                },
+                SPECIAL(POP) if !self.stack.is_empty() => { self.stack.pop(); },
+                SPECIAL(SWAP) if self.stack.len() >= 2 => { let length = self.stack.len(); self.stack.swap(length - 1, length - 2) },
+                SPECIAL(NGET) if !self.stack.is_empty() => {
+                    let index = self.stack.pop().unwrap_or_default();
+                    self.take(&index);
+                    match index.literal {
+                        Some(INTEGER(index)) if index >= 1 && index as usize <= self.stack.len() && self.stack[self.stack.len() - index as usize].sequences.is_empty() => {
+                            let copied = self.stack[self.stack.len() - index as usize].clone();
+                            self.stack.push(copied);
+                        },
+                        _ => break,
+                    }
+                },
+                SPECIAL(SEL) if self.stack.len() >= 3 => {
+                    let otherwise = self.stack.pop().unwrap_or_default();
+                    let then = self.stack.pop().unwrap_or_default();
+                    let condition = self.stack.pop().unwrap_or_default();
+                    self.take(&condition);
+                    self.stack.push(match condition.literal {
+                        Some(INTEGER(0) | BOOLEAN(false)) => otherwise,
+                        Some(INTEGER(_) | BOOLEAN(true)) => then,
+                        _ => then.either(otherwise),
+                    });
+                },
+                SPECIAL(EXEC) if !self.stack.is_empty() => {
+                    let executed = self.stack.pop().unwrap_or_default();
+                    self.take(&executed);
+                    match (executed.sequence_only(), executed.sequences.as_slice()) {
+                        (true, [(location, body)]) => self.pending.extend(body.iter().enumerate().rev().map(|(index, command)| (command.to_owned(), located(location, index)))),
+                        (true, sequences) => {
+                            // one run for each sequence it may be
+                            return sequences.iter().all(|(location, body)| {
+                                let mut flow = self.clone();
+                                flow.pending.extend(body.iter().enumerate().rev().map(|(index, command)| (command.to_owned(), located(location, index))));
+                                flows.len() < FLOWS && flow.follow(flows)
+                            });
+                        },
+                        (false, _) => break,
+                    }
+                },
+                SPECIAL(function) => match function.effect() {
+                    Some((inputs, outputs)) if self.stack.len() >= inputs => {
+                        let operands = self.stack.split_off(self.stack.len() - inputs);
+                        if matches!(function, ARITHMETIC(ADD | SUB | MUL | DIV | REM | POW | SHL | SHR)) && operands.iter().any(|operand| operand.truth) {
+                            self.mixed.push(location);
+                        }
+                        operands.iter().for_each(|operand| self.take(operand));
+                        let truth = matches!(function, ARITHMETIC(EQ | NE | LT | LE | GT | GE));
+                        self.stack.extend(std::iter::repeat_n(Tracked { data: true, truth, ..Tracked::default() }, outputs));
+                    },
+                    _ => break,
+                },
+                literal => self.stack.push(Tracked { data: true, literal: Some(literal), ..Tracked::default() }),
+            }
+        }
+        flows.push(self);
+        flows.len() <= FLOWS
+    }
+
+    // whether the value is gone from the stack by the end of the flow, or might still be used
+    fn dropped(&self, matches: impl Fn(&Tracked) -> bool) -> bool {
+        self.finished || !self.stack.iter().any(matches)
+    }
+}
+
+// lints seen following the runs of a program, on all of them for a value never used
+fn flow_lints(program: &PostfixProgram, lints: &mut Vec<PostfixLint>) {
+    use PostfixLintKind::*;
+    let arguments = (1..=program.paramsize as usize).rev().map(|index| Tracked { arguments: vec![index], data: true, ..Tracked::default() });
+    let count = program.variadic.then(|| Tracked { data: true, ..Tracked::default() });
+    let start = Flow {
+        stack: arguments.chain(count).collect(),
+        pending: program.commands.iter().enumerate().rev().map(|(index, command)| (command.to_owned(), vec![index])).collect(),
+        ..Flow::default()
+    };
+    let mut flows = Vec::new();
+    if !start.follow(&mut flows) {
+        return;
+    }
+    // the final top is the result: read, unless it can only be a sequence
+    let returned = flows.iter().all(|flow| flow.finished && flow.stack.last().is_some_and(Tracked::sequence_only));
+    flows.iter_mut().filter(|flow| flow.finished).for_each(|flow| match flow.stack.last().cloned() {
+        Some(top) if returned => flow.used.extend(top.sequences.iter().map(|(location, _)| location.to_owned())),
+        Some(top) => flow.take(&top),
+        None => (),
+    });
+    if returned {
+        let location = flows.iter().find_map(|flow| flow.stack.last().and_then(|top| top.sequences.first()).map(|(location, _)| location.to_owned())).unwrap_or_default();
+        lints.push(PostfixLint { kind: SEQUENCERESULT, location, position: None, message: "the program always ends with a sequence on top".to_string() });
+    }
+    (1..=program.paramsize as usize).filter(|index| flows.iter().all(|flow| !flow.read.contains(index) && flow.dropped(|value| value.arguments.contains(index)))).for_each(|index| {
+        lints.push(PostfixLint { kind: UNUSEDPARAMETER, location: Vec::new(), position: None, message: format!("parameter {} is never read", index) })
+    });
+    let mut pushed: Vec<&Vec<usize>> = flows.iter().flat_map(|flow| &flow.pushed).collect();
+    pushed.sort();
+    pushed.dedup();
+    pushed.into_iter().filter(|location| flows.iter().filter(|flow| flow.pushed.contains(location)).all(|flow| {
+        !flow.used.contains(location) && flow.dropped(|value| value.sequences.iter().any(|(pushed, _)| pushed == *location))
+    })).for_each(|location| {
+        lints.push(PostfixLint { kind: UNUSEDSEQUENCE, location: location.to_owned(), position: None, message: "the sequence is never run".to_string() })
+    });
+    flows.iter().flat_map(|flow| &flow.mixed).for_each(|location| {
+        lints.push(PostfixLint { kind: COMPARISONARITHMETIC, location: location.to_owned(), position: None, message: "the result of a comparison is used as a number".to_string() })
+    });
+}
+
+// every lint of the program, in the order of the commands they are about
+pub fn lint_program(program: &PostfixProgram) -> Vec<PostfixLint> {
+    let mut lints = Vec::new();
+    written_lints(&program.commands, &[], &mut lints);
+    flow_lints(program, &mut lints);
+    let mut unique: Vec<PostfixLint> = Vec::new();
+    lints.into_iter().for_each(|lint| match unique.iter().any(|seen| seen.kind == lint.kind && seen.location == lint.location) {
+        true => (),
+        false => unique.push(PostfixLint { position: program.position(&lint.location), ..lint }),
+    });
+    unique.sort_by(|left, right| left.location.cmp(&right.location));
+    unique
+}
+
+// lints named by id or name in an `allow(...)` anywhere in a comment, as in `; keep it: allow(L003, unused-parameter)`
+fn allowed(comment: &str) -> Vec<PostfixLintKind> {
+    comment.split("allow(").skip(1).filter_map(|rest| rest.split_once(')')).flat_map(|(names, _)| {
+        names.split(|character: char| character == ',' || character.is_whitespace()).filter_map(|name| {
+            PostfixLintKind::ALL.into_iter().find(|kind| kind.id() == name || kind.name() == name)
+        }).collect::<Vec<_>>()
+    }).collect()
+}
+
+// the stretch of source a comment at `at` speaks for, from the first token after it: the whole form that
+// token opens, or else the rest of the token's line; a comment before `(postfix` speaks for the program
+fn covered(tokens: &[Located<Token>], at: Position) -> Option<(Position, Position)> {
+    let start = tokens.iter().position(|(_, position)| *position > at)?;
+    let from = tokens[start].1;
+    let to = match tokens[start].0 {
+        Token::LEFT | Token::LEFTSQUARE => tokens[start..].iter().scan(0, |depth, (token, position)| {
+            *depth += match token {
+                Token::LEFT | Token::LEFTSQUARE => 1,
+                Token::RIGHT | Token::RIGHTSQUARE => -1,
+                _ => 0,
+            };
+            Some((*depth, *position))
+        }).find(|(depth, _)| *depth == 0).map(|(_, position)| position).unwrap_or(Position { line: u32::MAX, column: u32::MAX }),
+        _ => Position { line: from.line, column: u32::MAX },
+    };
+    Some((from, to))
+}
+
+fn lint_source(code: &str, file: Option<&Path>, config: &PostfixConfig, loader: &dyn ModuleLoader) -> Result<Vec<PostfixLint>, PostfixError> {
+    let (tokens, comments) = parse_code_to_tokens_and_comments(code)?;
+    let allowances: Vec<(Vec<PostfixLintKind>, (Position, Position))> = comments.iter()
+        .filter_map(|(text, at)| covered(&tokens, *at).map(|stretch| (allowed(text), stretch)))
+        .collect();
+    // lints of the whole program are taken to be where `(postfix` opens
+    let opening = tokens.windows(2).find(|pair| matches!(pair[1].0, Token::KEYWORD(PostfixKeyword::HEAD))).map(|pair| pair[0].1).unwrap_or_default();
+    let program = parse_tokens_to_program_with_loader(tokens, config, file, loader)?;
+    Ok(lint_program(&program).into_iter().filter(|lint| {
+        let position = lint.position.unwrap_or(opening);
+        !allowances.iter().any(|(kinds, (from, to))| kinds.contains(&lint.kind) && *from <= position && position <= *to)
+    }).collect())
+}
+
+// the lints of a program given as code, leaving out those its comments allow
+pub fn lint_code(code: &str, config: &PostfixConfig) -> Result<Vec<PostfixLint>, PostfixError> {
+    lint_source(code, None, config, &FileSystemLoader)
+}
+
+pub fn lint_file(path: &Path, config: &PostfixConfig, loader: &dyn ModuleLoader) -> Result<Vec<PostfixLint>, PostfixError> {
+    let code = loader.read(path).ok_or(PostfixError::SourceNotFound(path.to_owned()))?;
+    lint_source(&code, Some(path), config, loader)
+}
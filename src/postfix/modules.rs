@@ -0,0 +1,50 @@
+use std::{
+    fs,
+    path::{
+        Component,
+        Path,
+        PathBuf,
+    },
+};
+
+use super::errors::PostfixError;
+
+
+// where the source of programs and imported modules is read from
+pub trait ModuleLoader {
+    fn read(&self, path: &Path) -> Option<String>;
+
+    // the one path a module goes by however it is reached, which tells whether it is already being imported
+    fn resolve(&self, path: &Path) -> PathBuf {
+        without_current_directory(path)
+    }
+}
+
+// the path without its `.` components, the same file as far as can be told without a file system
+pub fn without_current_directory(path: &Path) -> PathBuf {
+    path.components().filter(|component| !matches!(component, Component::CurDir)).collect()
+}
+
+pub struct FileSystemLoader;
+
+impl ModuleLoader for FileSystemLoader {
+    fn read(&self, path: &Path) -> Option<String> {
+        fs::read_to_string(path).ok()
+    }
+
+    fn resolve(&self, path: &Path) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| without_current_directory(path))
+    }
+}
+
+// `(import name)` looks for `name.postfix` next to the importing file first, then along the search path
+pub fn find_module(name: &str, importer: Option<&Path>, search_path: &[PathBuf], loader: &dyn ModuleLoader) -> Result<(PathBuf, String), PostfixError> {
+    let filename = format!("{}.postfix", name);
+    let directory = importer.and_then(|path| path.parent()).unwrap_or(Path::new(""));
+    let candidates: Vec<PathBuf> = std::iter::once(directory.join(&filename))
+        .chain(search_path.iter().map(|directory| directory.join(&filename)))
+        .collect();
+    candidates.iter()
+        .find_map(|path| loader.read(path).map(|code| (loader.resolve(path), code)))
+        .ok_or(PostfixError::ModuleNotFound { name: name.to_string(), searched: candidates })
+}
@@ -0,0 +1,63 @@
+use std::{
+    fmt,
+    rc::Rc,
+};
+
+use super::{
+    errors::PostfixError,
+    prelude::prelude_names,
+    lexer::{
+        parse_code_to_tokens,
+        Token,
+    },
+};
+
+
+// a command provided by the host: pops `arity` integers (deepest first) and pushes the results
+pub type NativeFunction = Rc<dyn Fn(&[i32]) -> Result<Vec<i32>, String>>;
+
+#[derive(Clone)]
+pub struct NativeCommand {
+    pub name: String,
+    pub arity: usize,
+    pub function: NativeFunction,
+}
+
+impl fmt::Debug for NativeCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NativeCommand {{ name: {:?}, arity: {} }}", self.name, self.arity)
+    }
+}
+
+// natives are referred to by their position in the registry, see PostfixFunction::NATIVE
+#[derive(Debug,Clone,Default)]
+pub struct NativeRegistry {
+    commands: Vec<NativeCommand>,
+}
+
+impl NativeRegistry {
+
+    pub fn register<F>(mut self, name: &str, arity: usize, function: F) -> Result<Self, PostfixError>
+    where F: Fn(&[i32]) -> Result<Vec<i32>, String> + 'static {
+        // the name has to lex as a plain name, otherwise programs could never refer to it, and must not be
+        // taken by the prelude, whose routines are looked up before natives
+        match parse_code_to_tokens(name).as_deref() {
+            Ok([(Token::NAME(lexed), _)]) if lexed == name => match (self.lookup(name), prelude_names().iter().any(|defined| defined == name)) {
+                (Some(_), _) | (None, true) => Err(PostfixError::Redefinition(name.to_string())),
+                (None, false) => {
+                    self.commands.push(NativeCommand { name: name.to_string(), arity, function: Rc::new(function) });
+                    Ok(self)
+                },
+            },
+            _ => Err(PostfixError::InvalidNativeName(name.to_string())),
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<usize> {
+        self.commands.iter().position(|command| command.name == name)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&NativeCommand> {
+        self.commands.get(index)
+    }
+}
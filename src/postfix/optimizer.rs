@@ -0,0 +1,182 @@
+use super::{
+    config::PostfixConfig,
+    enums::{
+        apply_function,
+        PostfixCommand,
+        PostfixFunction,
+        PostfixProgram,
+    },
+};
+
+
+// semantics-preserving rewrites applied by the optimizer, each one on its own
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum PostfixRewrite {
+    // `3 4 add` into `7`, and any other pure command run on values pushed right before it
+    FOLDCONSTANTS,
+    // `1 (a) (b) sel` into `(a)`
+    SELECTKNOWN,
+    // `(a b) exec` into `a b`
+    INLINEEXEC,
+    // `7 pop` or `(a) pop` into nothing
+    DROPPUSHES,
+    // `1 nget pop` into nothing, when the copied value is known to be there
+    DROPCOPIES,
+    // `swap swap` into nothing, when two values are known to be there
+    CANCELSWAPS,
+    // `2 nget` into `5`, when the value copied is known to be 5
+    COPYCONSTANTS,
+}
+
+impl PostfixRewrite {
+    pub const ALL: [PostfixRewrite; 7] = [
+        PostfixRewrite::FOLDCONSTANTS,
+        PostfixRewrite::SELECTKNOWN,
+        PostfixRewrite::INLINEEXEC,
+        PostfixRewrite::DROPPUSHES,
+        PostfixRewrite::DROPCOPIES,
+        PostfixRewrite::CANCELSWAPS,
+        PostfixRewrite::COPYCONSTANTS,
+    ];
+}
+
+// what the optimizer knows of a value the stack is sure to hold
+#[derive(Debug,Clone,PartialEq)]
+enum Known {
+    // pushed by a literal
+    VALUE(PostfixCommand),
+    DATA,
+    SEQUENCE,
+    UNKNOWN,
+}
+
+// the values on top of the stack after `commands`, deepest first, given the ones known before them;
+// any value below them may or may not be there
+fn known_after(commands: &[PostfixCommand], before: &[Known]) -> Vec<Known> {
+    use PostfixCommand::*;
+    use PostfixFunction::*;
+    commands.iter().fold(before.to_owned(), |mut known, command| match command {
+        EXECUTE(_) => { known.push(Known::SEQUENCE); known },
+        INTEGER(_) | RATIONAL(_) | BOOLEAN(_) | STRING(_) | LIST(_) => { known.push(Known::VALUE(command.to_owned())); known },
+        SPECIAL(function) => match function.effect() {
+            Some((inputs, _)) if known.len() < inputs => Vec::new(),
+            Some((inputs, outputs)) => {
+                let taken = known.split_off(known.len() - inputs);
+                match (function, taken.as_slice()) {
+                    (SWAP, [below, above]) => known.extend([above.to_owned(), below.to_owned()]),
+                    (SEL, [_, then, otherwise]) if then == otherwise => known.push(then.to_owned()),
+                    (SEL | AT | GET, _) => known.push(Known::UNKNOWN),
+                    (_, _) => known.extend(std::iter::repeat_n(Known::DATA, outputs)),
+                };
+                known
+            },
+            None => Vec::new(),
+        },
+    })
+}
+
+// what is known of the value `index nget` copies
+fn copied(index: i32, known: &[Known]) -> Option<Known> {
+    match usize::try_from(index) {
+        Ok(index) if index >= 1 && index <= known.len() => Some(known[known.len() - index].to_owned()),
+        _ => None,
+    }
+}
+
+// values a command pushes without looking at the stack
+fn pushes(command: &PostfixCommand) -> bool {
+    !matches!(command, PostfixCommand::SPECIAL(_))
+}
+
+pub struct PostfixOptimizer {
+    rewrites: Vec<PostfixRewrite>,
+}
+
+impl PostfixOptimizer {
+
+    pub fn all() -> Self {
+        Self { rewrites: PostfixRewrite::ALL.to_vec() }
+    }
+
+    pub fn none() -> Self {
+        Self { rewrites: Vec::new() }
+    }
+
+    pub fn with(mut self, rewrite: PostfixRewrite) -> Self {
+        match self.rewrites.contains(&rewrite) {
+            true => self,
+            false => { self.rewrites.push(rewrite); self },
+        }
+    }
+
+    pub fn without(mut self, rewrite: PostfixRewrite) -> Self {
+        self.rewrites.retain(|enabled| *enabled != rewrite);
+        self
+    }
+
+    // the rewrite of the end of `commands`, as the number of commands it replaces and what replaces them
+    fn rewrite(&self, commands: &[PostfixCommand], before: &[Known], config: &PostfixConfig) -> Option<(usize, Vec<PostfixCommand>)> {
+        use PostfixCommand::*;
+        use PostfixFunction::*;
+        use PostfixRewrite::*;
+        let enabled = |rewrite| self.rewrites.contains(&rewrite);
+        let known = |count: usize| known_after(&commands[..commands.len() - count], before);
+        match commands {
+            [.., EXECUTE(body), SPECIAL(EXEC)] if enabled(INLINEEXEC) => Some((2, body.to_owned())),
+            [.., pushed, SPECIAL(POP)] if enabled(DROPPUSHES) && pushes(pushed) => Some((2, Vec::new())),
+            [.., SPECIAL(SWAP), SPECIAL(SWAP)] if enabled(CANCELSWAPS) && known(2).len() >= 2 => Some((2, Vec::new())),
+            [.., INTEGER(index), SPECIAL(NGET), SPECIAL(POP)] if enabled(DROPCOPIES) => match copied(*index, &known(3)) {
+                Some(Known::VALUE(_) | Known::DATA) => Some((3, Vec::new())),
+                _ => None,
+            },
+            [.., INTEGER(index), SPECIAL(NGET)] if enabled(COPYCONSTANTS) => match copied(*index, &known(2)) {
+                Some(Known::VALUE(value)) => Some((2, vec![value])),
+                _ => None,
+            },
+            [.., condition, then, otherwise, SPECIAL(SEL)] if enabled(SELECTKNOWN) && [condition, then, otherwise].iter().all(|command| pushes(command)) => {
+                apply_function(SEL, &[condition.to_owned(), then.to_owned(), otherwise.to_owned()], config).ok().map(|values| (4, values))
+            },
+            [.., SPECIAL(function)] if enabled(FOLDCONSTANTS) => match (function, function.effect()) {
+                (ABS | ARITHMETIC(_) | AT | CONCAT | INT | LEN | NEG | PUSH | STR | SWAP, Some((inputs, _))) if commands.len() > inputs => {
+                    let operands = &commands[commands.len() - 1 - inputs..commands.len() - 1];
+                    match operands.iter().all(pushes) {
+                        true => apply_function(*function, operands, config).ok().map(|values| (inputs + 1, values)),
+                        false => None,
+                    }
+                },
+                (_, _) => None,
+            },
+            _ => None,
+        }
+    }
+
+    // each command added in turn, rewriting the end of what is built so far for as long as a rewrite applies
+    fn optimize_with(&self, commands: &[PostfixCommand], before: &[Known], config: &PostfixConfig) -> Vec<PostfixCommand> {
+        let mut pending: Vec<PostfixCommand> = commands.iter().rev().cloned().collect();
+        let mut optimized: Vec<PostfixCommand> = Vec::new();
+        while let Some(command) = pending.pop() {
+            let command = match command {
+                PostfixCommand::EXECUTE(body) => PostfixCommand::EXECUTE(self.optimize_with(&body, &[], config)),
+                command => command,
+            };
+            optimized.push(command);
+            // what replaces the end is added again, so that rewrites carry on through it
+            if let Some((count, replacement)) = self.rewrite(&optimized, before, config) {
+                optimized.truncate(optimized.len() - count);
+                pending.extend(replacement.into_iter().rev());
+            }
+        }
+        optimized
+    }
+
+    // commands of a sequence run on a stack nothing is known of
+    pub fn optimize_commands(&self, commands: &[PostfixCommand], config: &PostfixConfig) -> Vec<PostfixCommand> {
+        self.optimize_with(commands, &[], config)
+    }
+
+    // the program made to run with the same result or error under `config`, in fewer steps
+    pub fn optimize(&self, program: &PostfixProgram, config: &PostfixConfig) -> PostfixProgram {
+        let arguments = vec![Known::DATA; program.paramsize as usize + program.variadic as usize];
+        PostfixProgram::new(program.paramsize, program.variadic, self.optimize_with(&program.commands, &arguments, config))
+    }
+}
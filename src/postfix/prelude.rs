@@ -0,0 +1,43 @@
+use super::{
+    lexer::{
+        parse_code_to_tokens,
+        Located,
+        Token,
+    },
+    config::{
+        PostfixConfig,
+        PostfixExtension,
+    },
+    errors::PostfixError,
+    enums::PostfixKeyword,
+};
+
+
+// bumped whenever a routine of the prelude changes behaviour
+pub const PRELUDE_VERSION: u32 = 1;
+
+// routines written in PostFix itself; each of them expands to core commands only
+pub const PRELUDE: &str = include_str!("prelude.postfix");
+
+// routines standing in for functions of the extended arithmetic, left out of dialects that have the builtins
+pub const ARITHMETIC_PRELUDE: &str = include_str!("prelude_arithmetic.postfix");
+
+// the plain names the prelude defines, which nothing else may take on as long as definitions come first
+pub fn prelude_names() -> Vec<String> {
+    [PRELUDE, ARITHMETIC_PRELUDE].into_iter().flat_map(|code| {
+        let tokens = parse_code_to_tokens(code).unwrap_or_default();
+        tokens.windows(2).filter_map(|window| match window {
+            [(Token::KEYWORD(PostfixKeyword::DEF), _), (Token::NAME(name), _)] => Some(name.to_owned()),
+            [(Token::KEYWORD(PostfixKeyword::DEF), _), (Token::KEYWORD(PostfixKeyword::FUNCTION(func)), _)] => func.name().map(str::to_owned),
+            _ => None,
+        }).collect::<Vec<_>>()
+    }).collect()
+}
+
+pub fn prelude_tokens(version: u32, config: &PostfixConfig) -> Result<Vec<Located<Token>>, PostfixError> {
+    match (version == PRELUDE_VERSION, config.extensions.contains(&PostfixExtension::MATH)) {
+        (true, true) => parse_code_to_tokens(PRELUDE),
+        (true, false) => Ok([parse_code_to_tokens(PRELUDE)?, parse_code_to_tokens(ARITHMETIC_PRELUDE)?].concat()),
+        (false, _) => Err(PostfixError::UnsupportedPreludeVersion { requested: version, available: PRELUDE_VERSION }),
+    }
+}
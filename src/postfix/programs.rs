@@ -1,57 +1,193 @@
+use std::path::Path;
+
 use super::{
     lexer::{
         parse_code_to_tokens,
+        Located,
+        Position,
         Token,
     },
     errors::PostfixError,
+    effects::check_program,
+    types::check_types,
+    prelude::prelude_tokens,
+    io::{
+        HostIo,
+        StandardIo,
+    },
+    modules::{
+        FileSystemLoader,
+        ModuleLoader,
+    },
+    config::{
+        PostfixConfig,
+        PostfixExtension,
+    },
     enums::{
         execute_command,
         PostfixKeyword,
         PostfixProgram,
         ProgramBuilder,
         PostfixCommand,
+        PostfixContext,
+        PostfixMemory,
+        PostfixState,
     }
 };
 
 
 
-pub fn parse_tokens_to_program(tokens: Vec<Token>) -> Result<PostfixProgram, PostfixError> {
+// distinct plain names up to the right parenthesis closing them, followed by the rest of the program
+fn parse_parameters(tokens: &[Token]) -> Result<(Vec<String>, &[Token]), PostfixError> {
+    let position = tokens.iter().position(|token| matches!(token, Token::RIGHT)).ok_or(PostfixError::MalformedParameters)?;
+    let parameters = tokens[..position].iter().try_fold(Vec::new(), |mut parameters: Vec<String>, token| match token {
+        Token::NAME(name) if !parameters.contains(name) => { parameters.push(name.to_owned()); Ok(parameters) },
+        _ => Err(PostfixError::MalformedParameters),
+    })?;
+    Ok((parameters, &tokens[position + 1..]))
+}
+
+pub fn parse_tokens_to_program(tokens: Vec<Located<Token>>, config: &PostfixConfig) -> Result<PostfixProgram, PostfixError> {
+    parse_tokens_to_program_with_loader(tokens, config, None, &FileSystemLoader)
+}
+
+pub fn parse_tokens_to_program_with_loader(tokens: Vec<Located<Token>>, config: &PostfixConfig, file: Option<&Path>, loader: &dyn ModuleLoader) -> Result<PostfixProgram, PostfixError> {
     use Token::*;
-    if let [LEFT, KEYWORD(PostfixKeyword::HEAD), NUMBER(paramsize), remaining_tokens @ .., RIGHT] = tokens.as_slice() {
-        Ok(remaining_tokens.iter().fold(
-            Ok(ProgramBuilder::new(*paramsize as usize)),
-            |builder, token| builder?.consume(*token)
-        )?.build())
-    } else { Err(PostfixError::PostfixShouldBeginWithLeftPostfixAndEndWithRight) }
+    let (tokens, positions): (Vec<Token>, Vec<Position>) = tokens.into_iter().unzip();
+    let (paramsize, variadic, parameters, remaining_tokens) = match tokens.as_slice() {
+        [LEFT, KEYWORD(PostfixKeyword::HEAD), NUMBER(paramsize), PLUS, remaining_tokens @ .., RIGHT] => (*paramsize as usize, true, Vec::new(), remaining_tokens),
+        [LEFT, KEYWORD(PostfixKeyword::HEAD), STAR, remaining_tokens @ .., RIGHT] => (0, true, Vec::new(), remaining_tokens),
+        [LEFT, KEYWORD(PostfixKeyword::HEAD), NUMBER(paramsize), remaining_tokens @ .., RIGHT] => (*paramsize as usize, false, Vec::new(), remaining_tokens),
+        [LEFT, KEYWORD(PostfixKeyword::HEAD), LEFT, header_tokens @ .., RIGHT] => {
+            let (parameters, remaining_tokens) = parse_parameters(header_tokens)?;
+            (parameters.len(), false, parameters, remaining_tokens)
+        },
+        _ => return Err(PostfixError::PostfixShouldBeginWithLeftPostfixAndEndWithRight),
+    };
+    let builder = ProgramBuilder::new(paramsize, config).with_loader(loader).with_parameters(parameters);
+    let builder = match variadic {
+        true => builder.with_variadic_arguments(),
+        false => builder,
+    };
+    let builder = match file {
+        Some(path) => builder.with_file(path),
+        None => builder,
+    };
+    let builder = match config.prelude {
+        Some(version) => builder.with_prelude(prelude_tokens(version, config)?)?,
+        None => builder,
+    };
+    // the commands follow the header, up to the last token closing the program
+    let offset = tokens.len() - 1 - remaining_tokens.len();
+    remaining_tokens.iter().cloned().zip(positions[offset..].iter().copied()).try_fold(
+        builder,
+        |builder, token| builder.consume(token)
+    )?.build()
+}
+
+fn trace_state(state: &PostfixState) {
+    match state.memory.is_empty() {
+        true => println! ("{:?} || {:?}", state.commands, state.stack),
+        false => println! ("{:?} || {:?} || {:?}", state.commands, state.stack, state.memory),
+    }
+}
+
+fn check(program: PostfixProgram, config: &PostfixConfig) -> Result<PostfixProgram, PostfixError> {
+    if config.check_stack {
+        check_program(&program)?;
+    }
+    if config.check_types {
+        check_types(&program, config)?;
+    }
+    Ok(program)
+}
+
+fn execute_program(program: &PostfixProgram, arguments: &Vec<i32>, config: &PostfixConfig, io: &mut dyn HostIo) -> Result<i32, PostfixError> {
+    execute_program_counting_steps(program, arguments, config, io).0
 }
 
-fn execute_program(program: &PostfixProgram, arguments: &Vec<i32>) -> Result<i32, PostfixError> {
-    if arguments.len() != program.paramsize as usize {
+// the outcome of a run along with the number of commands it went through
+pub fn execute_program_counting_steps(program: &PostfixProgram, arguments: &Vec<i32>, config: &PostfixConfig, io: &mut dyn HostIo) -> (Result<i32, PostfixError>, usize) {
+    let mut steps = 0;
+    let output = execute_program_with_counter(program, arguments, config, io, &mut steps);
+    (output, steps)
+}
+
+fn execute_program_with_counter(program: &PostfixProgram, arguments: &Vec<i32>, config: &PostfixConfig, io: &mut dyn HostIo, steps: &mut usize) -> Result<i32, PostfixError> {
+    let accepted = match program.variadic {
+        true => arguments.len() >= program.paramsize as usize,
+        false => arguments.len() == program.paramsize as usize,
+    };
+    if !accepted {
         Err(PostfixError::WrongNumberOfArguments { expected: program.paramsize as usize, actual: arguments.to_owned() })
     } else {
-        let mut stack = arguments.iter().rev().map(|&n| PostfixCommand::INTEGER(n)).collect();
-        let owned_program_commands = program.commands.to_owned();
-        let mut commands: Vec<PostfixCommand> = owned_program_commands.iter().rev().map(|command| command.to_owned()).collect();
+        let memory_size = match config.extensions.contains(&PostfixExtension::MEMORY) {
+            true => config.memory_size,
+            false => 0,
+        };
+        let mut state = PostfixState {
+            commands: program.commands.iter().rev().map(|command| command.to_owned()).collect(),
+            stack: arguments.iter().rev().map(|&n| PostfixCommand::INTEGER(n)).chain(match program.variadic {
+                true => Some(PostfixCommand::INTEGER(arguments.len() as i32)),
+                false => None,
+            }).collect(),
+            memory: PostfixMemory::new(memory_size),
+            handlers: Vec::new(),
+        };
+        let mut context = PostfixContext { config, io };
         loop {
-            println! ("{:?} || {:?}", commands, stack);
-            match commands.pop() {
+            state.leave_finished_handlers();
+            if config.trace {
+                trace_state(&state);
+            }
+            match state.commands.pop() {
                 None => break,
-                Some(command) => execute_command(&command, &mut commands, &mut stack)?
+                Some(command) => {
+                    *steps += 1;
+                    match execute_command(&command, &mut state, &mut context) {
+                        Ok(()) => (),
+                        Err(error) => state.recover(error)?,
+                    }
+                }
             }
         };
-        match stack.as_slice() {
+        match state.stack.as_slice() {
             [.., PostfixCommand::INTEGER(n)] => Ok(*n),
-            [.., command] => Err(PostfixError::NonNumeralFinalState { command: command.clone() }),
-            [] => Err(PostfixError::EmptyStackFinalState), 
+            [.., PostfixCommand::RATIONAL(value)] => config.conversion.convert(*value).ok_or(PostfixError::UnrepresentableFinalState { value: *value }),
+            [.., command] => Err(PostfixError::NonNumeralFinalState { command: command.clone(), memory: state.memory }),
+            [] => Err(PostfixError::EmptyStackFinalState { memory: state.memory }),
         }
         
     }
 }
 
 pub fn compile_and_run(code: &str, arguments: &Vec<i32>) -> Result<i32, PostfixError> {
+    compile_and_run_with_config(code, arguments, &PostfixConfig::default())
+}
+
+pub fn compile_and_run_with_config(code: &str, arguments: &Vec<i32>, config: &PostfixConfig) -> Result<i32, PostfixError> {
+    compile_and_run_with_io(code, arguments, config, &mut StandardIo)
+}
+
+pub fn compile_and_run_with_io(code: &str, arguments: &Vec<i32>, config: &PostfixConfig, io: &mut dyn HostIo) -> Result<i32, PostfixError> {
     println! ("{:?}", code);
     let tokens = parse_code_to_tokens(code)?;
-    let program = parse_tokens_to_program(tokens)?;
-    let output = execute_program(&program, arguments)?;
+    let program = check(parse_tokens_to_program(tokens, config)?, config)?;
+    let output = execute_program(&program, arguments, config, io)?;
+    Ok(output)
+}
+
+pub fn compile_file(path: &Path, config: &PostfixConfig, loader: &dyn ModuleLoader) -> Result<PostfixProgram, PostfixError> {
+    let code = loader.read(path).ok_or(PostfixError::SourceNotFound(path.to_owned()))?;
+    if config.trace {
+        println! ("{:?}", code);
+    }
+    let tokens = parse_code_to_tokens(&code)?;
+    parse_tokens_to_program_with_loader(tokens, config, Some(path), loader)
+}
+
+pub fn compile_file_and_run(path: &Path, arguments: &Vec<i32>, config: &PostfixConfig, loader: &dyn ModuleLoader, io: &mut dyn HostIo) -> Result<i32, PostfixError> {
+    let program = check(compile_file(path, config, loader)?, config)?;
+    let output = execute_program(&program, arguments, config, io)?;
     Ok(output)
-}
\ No newline at end of file
+}
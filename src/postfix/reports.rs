@@ -0,0 +1,37 @@
+use super::{
+    config::PostfixConfig,
+    effects::{
+        check_program,
+        sequence_effect,
+    },
+    energy::step_bound,
+    enums::PostfixProgram,
+    types::infer_types,
+};
+
+
+// what the static analyses tell of a program, one line each, as printed by `postfix analyze`
+pub fn analysis_report(program: &PostfixProgram, config: &PostfixConfig) -> String {
+    let parameters = match program.variadic {
+        true => format!("{} or more", program.paramsize),
+        false => format!("{}", program.paramsize),
+    };
+    let effect = match sequence_effect(&program.commands) {
+        Some(effect) => format!("{} -> {}", effect.inputs, effect.outputs),
+        None => String::from("unknown"),
+    };
+    let stack = match check_program(program) {
+        Ok(()) => String::from("ok"),
+        Err(error) => format!("{:?}", error),
+    };
+    let types = match infer_types(program, config) {
+        Ok(Some(types)) => format!("{:?}", types),
+        Ok(None) => String::from("unknown"),
+        Err(error) => format!("{:?}", error),
+    };
+    let steps = match step_bound(program) {
+        Some(steps) => format!("at most {}", steps),
+        None => String::from("unbounded"),
+    };
+    format!("parameters: {}\nstack effect: {}\nstack check: {}\ntypes: {}\nsteps: {}\n", parameters, effect, stack, types, steps)
+}
@@ -0,0 +1,107 @@
+use super::{
+    config::PostfixConfig,
+    enums::{
+        PostfixCommand,
+        PostfixFunction,
+        PostfixProgram,
+    },
+    errors::PostfixError,
+    optimizer::PostfixOptimizer,
+};
+
+
+// how the commands of a program get at one of its parameters: indices of the pop dropping it, of the nget
+// indices reaching past it and of those reaching it
+pub struct ParameterUses {
+    pub pop: Option<usize>,
+    pub lowered: Vec<usize>,
+    pub reads: Vec<usize>,
+}
+
+// None unless the parameter is only ever copied by nget and moved down by the commands above it until it
+// is dropped, or left below the result
+pub fn parameter_uses(program: &PostfixProgram, parameter: usize) -> Option<ParameterUses> {
+    use PostfixCommand::*;
+    use PostfixFunction::*;
+    if program.variadic {
+        return None;
+    }
+    let mut stack: Vec<bool> = (1..=program.paramsize as usize).rev().map(|position| position == parameter).collect();
+    let mut uses = ParameterUses { pop: None, lowered: Vec::new(), reads: Vec::new() };
+    for (index, command) in program.commands.iter().enumerate() {
+        let depth = stack.iter().rev().position(|marked| *marked)? + 1;
+        match (command, index.checked_sub(1).map(|previous| &program.commands[previous])) {
+            (SPECIAL(POP), _) if depth == 1 => return Some(ParameterUses { pop: Some(index), ..uses }),
+            // the index was pushed right before, and is on top of the argument
+            (SPECIAL(NGET), Some(INTEGER(reach))) => {
+                match usize::try_from(*reach) {
+                    Ok(reach) if reach == depth - 1 => uses.reads.push(index - 1),
+                    Ok(reach) if reach > depth - 1 => uses.lowered.push(index - 1),
+                    _ => (),
+                };
+                stack.pop();
+                stack.push(false);
+            },
+            (SPECIAL(NGET), _) => return None,
+            (SPECIAL(function), _) => match function.effect() {
+                Some((inputs, outputs)) if inputs < depth => {
+                    stack.truncate(stack.len() - inputs);
+                    stack.extend(std::iter::repeat_n(false, outputs));
+                },
+                _ => return None,
+            },
+            (_, _) => stack.push(false),
+        }
+    }
+    match stack.last() {
+        Some(false) => Some(uses),
+        Some(true) | None => None,
+    }
+}
+
+// the program without the parameter, its value copied in wherever it is read
+fn substitute(program: &PostfixProgram, parameter: usize, value: i32) -> Result<PostfixProgram, PostfixError> {
+    use PostfixCommand::*;
+    let ParameterUses { pop, lowered, reads } = parameter_uses(program, parameter).ok_or(PostfixError::UnsupportedSpecialization)?;
+    let commands = program.commands.iter().enumerate().filter(|(index, _)| Some(*index) != pop && !reads.contains(&index.wrapping_sub(1))).map(|(index, command)| match command {
+        INTEGER(_) if reads.contains(&index) => INTEGER(value),
+        INTEGER(reach) if lowered.contains(&index) => INTEGER(reach - 1),
+        command => command.to_owned(),
+    }).collect();
+    Ok(PostfixProgram::new(program.paramsize - 1, false, commands))
+}
+
+// commands that give back the stack the program expects, from the stack of the residual program, for known
+// arguments that all lie above the second unknown one: those above the first unknown one are pushed in their
+// place, those right below it are pushed and swapped under it
+fn prologue(known: &[Option<i32>]) -> Vec<PostfixCommand> {
+    use PostfixCommand::*;
+    let first = known.iter().position(|argument| argument.is_none()).unwrap_or(known.len());
+    let below = known.get(first + 1..).unwrap_or(&[]);
+    below.iter().rev().flatten().flat_map(|value| [INTEGER(*value), SPECIAL(PostfixFunction::SWAP)])
+        .chain(known[..first].iter().rev().flatten().map(|value| INTEGER(*value))).collect()
+}
+
+// the program over the arguments left unknown, in the same order, with the known ones given in `known` at
+// the position of each argument; sequences run or selected with what is then known are inlined. A known
+// argument below the second unknown one is copied in where it is read, which the program must do with nget.
+pub fn specialize(program: &PostfixProgram, known: &[Option<i32>], config: &PostfixConfig) -> Result<PostfixProgram, PostfixError> {
+    match (program.variadic, known.len() == program.paramsize as usize) {
+        (true, _) => Err(PostfixError::UnsupportedSpecialization),
+        (false, false) => Err(PostfixError::WrongNumberOfKnownArguments { expected: program.paramsize as usize, actual: known.len() }),
+        (false, true) => {
+            let second = known.iter().enumerate().filter(|(_, argument)| argument.is_none()).nth(1).map_or(known.len(), |(index, _)| index);
+            // deepest first, which leaves the parameters above in place
+            let program = known.iter().enumerate().skip(second).rev().try_fold(PostfixProgram::new(program.paramsize, false, program.commands.to_owned()), |program, (index, argument)| match argument {
+                Some(value) => substitute(&program, index + 1, *value),
+                None => Ok(program),
+            })?;
+            let residual = PostfixProgram::new(
+                known.iter().filter(|argument| argument.is_none()).count() as u32,
+                false,
+                prologue(&known[..second]).into_iter().chain(program.commands).collect(),
+            );
+            Ok(PostfixOptimizer::all().optimize(&residual, config))
+        },
+    }
+}
@@ -0,0 +1,224 @@
+use super::{
+    config::PostfixConfig,
+    enums::{
+        PostfixArithmetic,
+        PostfixCommand,
+        PostfixFunction,
+        PostfixProgram,
+    },
+    equivalence::{
+        check_equivalence,
+        same_outcome,
+        EquivalenceBounds,
+        PostfixEquivalence,
+    },
+    errors::PostfixError,
+    io::SilentIo,
+    programs::execute_program_counting_steps,
+    symbolic::argument_vectors,
+};
+
+
+// what a program has to give for the arguments it is run on
+type Example = (Vec<i32>, Result<i32, PostfixError>);
+
+// searches core programs from the shortest up, a sequence pushed counting one plus the length of its body
+pub struct PostfixSuperoptimizer {
+    literals: Vec<i32>,
+    functions: Vec<PostfixFunction>,
+    max_length: usize,
+    // longest body of a sequence a candidate pushes
+    max_body_length: usize,
+    // values the arguments of the test vectors are taken from
+    test_values: Vec<i32>,
+    // most test vectors a program is run on, every way of taking its arguments from the test values
+    max_examples: usize,
+    bounds: EquivalenceBounds,
+}
+
+impl Default for PostfixSuperoptimizer {
+    fn default() -> Self {
+        use PostfixArithmetic::*;
+        use PostfixFunction::*;
+        Self {
+            literals: vec![0, 1, 2],
+            functions: vec![ARITHMETIC(ADD), ARITHMETIC(SUB), ARITHMETIC(MUL), ARITHMETIC(DIV), ARITHMETIC(REM), ARITHMETIC(EQ), ARITHMETIC(LT), ARITHMETIC(GT), POP, SWAP, NGET, SEL, EXEC],
+            max_length: 4,
+            max_body_length: 2,
+            test_values: vec![0, 1, -1, 2, -2, 3],
+            max_examples: 10_000,
+            bounds: EquivalenceBounds::default(),
+        }
+    }
+}
+
+// a command that only undoes or repeats what the one before it did, so that a shorter candidate does the same
+fn redundant(previous: Option<&PostfixCommand>, command: &PostfixCommand) -> bool {
+    use PostfixCommand::*;
+    use PostfixFunction::*;
+    match (previous, command) {
+        (Some(SPECIAL(SWAP)), SPECIAL(SWAP)) => true,
+        (Some(EXECUTE(_)), SPECIAL(EXEC)) => true,
+        (Some(pushed), SPECIAL(POP)) => !matches!(pushed, SPECIAL(_)),
+        (_, _) => false,
+    }
+}
+
+// the height of the stack once `command` has run, None when it cannot be told, Err when it must underflow
+fn height_after(height: Option<usize>, command: &PostfixCommand) -> Result<Option<usize>, ()> {
+    match (height, command) {
+        (None, _) => Ok(None),
+        (Some(height), PostfixCommand::SPECIAL(function)) => match function.effect() {
+            Some((inputs, _)) if height < inputs => Err(()),
+            Some((inputs, outputs)) => Ok(Some(height - inputs + outputs)),
+            None => Ok(None),
+        },
+        (Some(height), _) => Ok(Some(height + 1)),
+    }
+}
+
+// candidates built from the atoms and the sequences of `bodies`, indexed by their length
+struct Enumeration {
+    atoms: Vec<PostfixCommand>,
+    bodies: Vec<Vec<Vec<PostfixCommand>>>,
+    // skip candidates that must underflow
+    prune: bool,
+}
+
+impl Enumeration {
+    fn new(atoms: Vec<PostfixCommand>, max_body_length: usize, prune: bool) -> Self {
+        let mut enumeration = Enumeration { atoms, bodies: Vec::new(), prune };
+        (0..=max_body_length).for_each(|length| {
+            let mut bodies = Vec::new();
+            enumeration.extend(&mut Vec::new(), length, None, &mut |commands| { bodies.push(commands.to_vec()); false });
+            enumeration.bodies.push(bodies);
+        });
+        enumeration
+    }
+
+    // each way to add `remaining` to `commands` handed to `visit` in turn, until it returns true
+    fn extend(&self, commands: &mut Vec<PostfixCommand>, remaining: usize, height: Option<usize>, visit: &mut dyn FnMut(&[PostfixCommand]) -> bool) -> bool {
+        if remaining == 0 {
+            return visit(commands);
+        }
+        let sequences = self.bodies.iter().enumerate().take(remaining).flat_map(|(length, bodies)| bodies.iter().map(move |body| (length + 1, PostfixCommand::EXECUTE(body.to_owned()))));
+        let atoms = self.atoms.iter().map(|atom| (1, atom.to_owned()));
+        for (length, command) in atoms.chain(sequences) {
+            if redundant(commands.last(), &command) {
+                continue;
+            }
+            let height = match height_after(height, &command) {
+                Err(()) if self.prune => continue,
+                Err(()) => None,
+                Ok(height) => height,
+            };
+            commands.push(command);
+            let found = self.extend(commands, remaining - length, height, visit);
+            commands.pop();
+            if found {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+fn literals(commands: &[PostfixCommand]) -> Vec<i32> {
+    commands.iter().flat_map(|command| match command {
+        PostfixCommand::INTEGER(n) => vec![*n],
+        PostfixCommand::EXECUTE(body) => literals(body),
+        _ => Vec::new(),
+    }).collect()
+}
+
+// the length of some commands as candidates are measured
+fn length(commands: &[PostfixCommand]) -> usize {
+    commands.iter().map(|command| match command {
+        PostfixCommand::EXECUTE(body) => 1 + length(body),
+        _ => 1,
+    }).sum()
+}
+
+impl PostfixSuperoptimizer {
+
+    pub fn with_literals(mut self, literals: Vec<i32>) -> Self {
+        self.literals = literals;
+        self
+    }
+
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    pub fn with_max_body_length(mut self, max_body_length: usize) -> Self {
+        self.max_body_length = max_body_length;
+        self
+    }
+
+    pub fn with_test_values(mut self, test_values: Vec<i32>) -> Self {
+        self.test_values = test_values;
+        self
+    }
+
+    pub fn with_max_examples(mut self, max_examples: usize) -> Self {
+        self.max_examples = max_examples;
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: EquivalenceBounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    // the first candidate, shortest first and no longer than `max_length`, to give what every example expects and be accepted
+    fn search(&self, paramsize: u32, max_length: usize, examples: &[Example], literals: &[i32], config: &PostfixConfig, accept: &dyn Fn(&PostfixProgram) -> bool) -> Option<PostfixProgram> {
+        let mut atoms: Vec<PostfixCommand> = Vec::new();
+        self.literals.iter().chain(literals).for_each(|literal| match atoms.contains(&PostfixCommand::INTEGER(*literal)) {
+            true => (),
+            false => atoms.push(PostfixCommand::INTEGER(*literal)),
+        });
+        atoms.extend(self.functions.iter().map(|function| PostfixCommand::SPECIAL(*function)));
+        // a candidate that must underflow can only stand for a program that fails
+        let prune = examples.iter().all(|(_, expected)| expected.is_ok());
+        let enumeration = Enumeration::new(atoms, self.max_body_length, prune);
+        let mut found = None;
+        (0..=max_length).any(|length| enumeration.extend(&mut Vec::new(), length, Some(paramsize as usize), &mut |commands| {
+            let candidate = PostfixProgram::new(paramsize, false, commands.to_vec());
+            let agrees = examples.iter().all(|(arguments, expected)| same_outcome(&execute_program_counting_steps(&candidate, arguments, config, &mut SilentIo).0, expected));
+            match agrees && accept(&candidate) {
+                true => { found = Some(candidate); true },
+                false => false,
+            }
+        }));
+        found
+    }
+
+    // the shortest core program found to agree with `program` on the test vectors and within the bounds
+    // of an equivalence check, None when there is none shorter than the program and up to the maximum length;
+    // a program with more parameters than the test vectors can cover is not taken
+    pub fn superoptimize(&self, program: &PostfixProgram, config: &PostfixConfig) -> Result<Option<PostfixProgram>, PostfixError> {
+        let covered = self.test_values.len().checked_pow(program.paramsize).is_some_and(|total| total <= self.max_examples);
+        if program.variadic || !covered {
+            return Err(PostfixError::UnsupportedSuperoptimization);
+        }
+        let config = config.clone().without_trace();
+        let examples: Vec<Example> = argument_vectors(program.paramsize as usize, &self.test_values).map(|arguments| {
+            let (outcome, _) = execute_program_counting_steps(program, &arguments, &config, &mut SilentIo);
+            (arguments, outcome)
+        }).collect();
+        let accept = |candidate: &PostfixProgram| matches!(check_equivalence(program, candidate, &self.bounds, &config), Ok(PostfixEquivalence::EQUIVALENT { .. }));
+        let max_length = match length(&program.commands) {
+            0 => return Ok(None),
+            length => self.max_length.min(length - 1),
+        };
+        Ok(self.search(program.paramsize, max_length, &examples, &literals(&program.commands), &config, &accept))
+    }
+
+    // the shortest core program found to give each of the expected results for its arguments
+    pub fn synthesize(&self, paramsize: u32, examples: &[(Vec<i32>, i32)], config: &PostfixConfig) -> Option<PostfixProgram> {
+        let config = config.clone().without_trace();
+        let examples: Vec<Example> = examples.iter().map(|(arguments, expected)| (arguments.to_owned(), Ok(*expected))).collect();
+        self.search(paramsize, self.max_length, &examples, &[], &config, &|_| true)
+    }
+}
@@ -0,0 +1,499 @@
+use std::{
+    collections::HashMap,
+    fmt,
+};
+
+use super::{
+    config::PostfixConfig,
+    enums::{
+        apply_function,
+        PostfixArithmetic,
+        PostfixCommand,
+        PostfixFunction,
+        PostfixMemory,
+        PostfixProgram,
+    },
+    errors::PostfixError,
+};
+
+
+// a value computed from the arguments of a program, the first argument being ARGUMENT(1)
+#[derive(Debug,Clone,PartialEq)]
+pub enum PostfixExpression {
+    ARGUMENT(usize),
+    INTEGER(i32),
+    ARITHMETIC(PostfixArithmetic, Box<PostfixExpression>, Box<PostfixExpression>),
+}
+
+impl PostfixExpression {
+
+    // the value for the given arguments, failing the way the interpreter would, or for want of an argument
+    pub fn evaluate(&self, arguments: &[i32]) -> Result<i32, PostfixError> {
+        use PostfixExpression::*;
+        match self {
+            ARGUMENT(index) => match index.checked_sub(1).and_then(|position| arguments.get(position)) {
+                Some(argument) => Ok(*argument),
+                None => Err(PostfixError::WrongNumberOfArguments { expected: *index, actual: arguments.to_vec() }),
+            },
+            INTEGER(n) => Ok(*n),
+            ARITHMETIC(operator, pre, post) => {
+                let operands = [PostfixCommand::INTEGER(pre.evaluate(arguments)?), PostfixCommand::INTEGER(post.evaluate(arguments)?)];
+                match apply_function(PostfixFunction::ARITHMETIC(*operator), &operands, &PostfixConfig::core())?.as_slice() {
+                    [PostfixCommand::INTEGER(n)] => Ok(*n),
+                    _ => Err(PostfixError::WrongTypeOfFunctionArguments { function: PostfixFunction::ARITHMETIC(*operator) }),
+                }
+            },
+        }
+    }
+
+    // the last argument the expression reads, 0 when it reads none
+    fn arguments(&self) -> usize {
+        use PostfixExpression::*;
+        match self {
+            ARGUMENT(index) => *index,
+            INTEGER(_) => 0,
+            ARITHMETIC(_, pre, post) => pre.arguments().max(post.arguments()),
+        }
+    }
+
+    fn compares(&self) -> bool {
+        use PostfixArithmetic::*;
+        matches!(self, PostfixExpression::ARITHMETIC(EQ | NE | GT | GE | LT | LE, _, _))
+    }
+}
+
+impl fmt::Display for PostfixExpression {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        use PostfixArithmetic::*;
+        use PostfixExpression::*;
+        match self {
+            ARGUMENT(index) => write!(formatter, "arg{}", index),
+            INTEGER(n) => write!(formatter, "{}", n),
+            ARITHMETIC(operator, pre, post) => match operator {
+                ADD => write!(formatter, "({} + {})", pre, post),
+                SUB => write!(formatter, "({} - {})", pre, post),
+                MUL => write!(formatter, "({} * {})", pre, post),
+                DIV => write!(formatter, "({} / {})", pre, post),
+                REM => write!(formatter, "({} % {})", pre, post),
+                EQ => write!(formatter, "({} = {})", pre, post),
+                LT => write!(formatter, "({} < {})", pre, post),
+                GT => write!(formatter, "({} > {})", pre, post),
+                operator => write!(formatter, "{}({}, {})", operator.name(), pre, post),
+            },
+        }
+    }
+}
+
+// what a decision is about: whether an expression is other than zero, or whether computing it overflows
+#[derive(Debug,Copy,Clone,PartialEq)]
+pub enum PostfixTest {
+    NONZERO,
+    OVERFLOWS,
+}
+
+// a decision taken on the way: whether `expression` passes `test`
+#[derive(Debug,Clone,PartialEq)]
+pub struct PostfixCondition {
+    pub expression: PostfixExpression,
+    pub test: PostfixTest,
+    pub holds: bool,
+}
+
+impl PostfixCondition {
+    fn evaluate(&self, arguments: &[i32]) -> bool {
+        match (self.test, self.expression.evaluate(arguments)) {
+            (PostfixTest::NONZERO, Ok(value)) => (value != 0) == self.holds,
+            (PostfixTest::OVERFLOWS, Ok(_)) => !self.holds,
+            (PostfixTest::OVERFLOWS, Err(PostfixError::ArithmeticOverflow { .. })) => self.holds,
+            (_, Err(_)) => false,
+        }
+    }
+}
+
+impl fmt::Display for PostfixCondition {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match (self.test, self.expression.compares(), self.holds) {
+            (PostfixTest::OVERFLOWS, _, true) => write!(formatter, "{} overflows", self.expression),
+            (PostfixTest::OVERFLOWS, _, false) => write!(formatter, "{} fits", self.expression),
+            (PostfixTest::NONZERO, true, true) => write!(formatter, "{}", self.expression),
+            (PostfixTest::NONZERO, true, false) => write!(formatter, "not {}", self.expression),
+            (PostfixTest::NONZERO, false, true) => write!(formatter, "{} != 0", self.expression),
+            (PostfixTest::NONZERO, false, false) => write!(formatter, "{} = 0", self.expression),
+        }
+    }
+}
+
+// the least and greatest value an expression can take
+type Interval = (i64, i64);
+
+const INTEGERS: Interval = (i32::MIN as i64, i32::MAX as i64);
+
+fn truth(always: bool, never: bool) -> Interval {
+    match (always, never) {
+        (true, _) => (1, 1),
+        (_, true) => (0, 0),
+        (_, _) => (0, 1),
+    }
+}
+
+// the values `expression` can take with the arguments within `bounds`; a result beyond an integer overflows,
+// which ends the path with an error instead
+fn interval(expression: &PostfixExpression, bounds: &HashMap<usize, Interval>) -> Interval {
+    use PostfixArithmetic::*;
+    match expression {
+        PostfixExpression::ARGUMENT(index) => bounds.get(index).copied().unwrap_or(INTEGERS),
+        PostfixExpression::INTEGER(n) => (*n as i64, *n as i64),
+        PostfixExpression::ARITHMETIC(operator, pre, post) => {
+            let ((pre_low, pre_high), (post_low, post_high)) = (interval(pre, bounds), interval(post, bounds));
+            let (low, high) = match operator {
+                ADD | SUB | MUL => range(*operator, (pre_low, pre_high), (post_low, post_high)),
+                LT => truth(pre_high < post_low, pre_low >= post_high),
+                LE => truth(pre_high <= post_low, pre_low > post_high),
+                GT => truth(pre_low > post_high, pre_high <= post_low),
+                GE => truth(pre_low >= post_high, pre_high < post_low),
+                EQ => truth(pre_low == pre_high && post_low == post_high && pre_low == post_low, pre_high < post_low || post_high < pre_low),
+                NE => truth(pre_high < post_low || post_high < pre_low, pre_low == pre_high && post_low == post_high && pre_low == post_low),
+                _ => INTEGERS,
+            };
+            match (low.max(INTEGERS.0), high.min(INTEGERS.1)) {
+                (low, high) if low <= high => (low, high),
+                (_, _) => INTEGERS,
+            }
+        },
+    }
+}
+
+// the values adding, subtracting or multiplying values within `pre` and `post` can give, overflowing or not
+fn range(operator: PostfixArithmetic, (pre_low, pre_high): Interval, (post_low, post_high): Interval) -> Interval {
+    use PostfixArithmetic::*;
+    match operator {
+        ADD => (pre_low + post_low, pre_high + post_high),
+        SUB => (pre_low - post_high, pre_high - post_low),
+        MUL => {
+            let products = [pre_low * post_low, pre_low * post_high, pre_high * post_low, pre_high * post_high];
+            (products.iter().copied().min().unwrap_or(INTEGERS.0), products.iter().copied().max().unwrap_or(INTEGERS.1))
+        },
+        _ => INTEGERS,
+    }
+}
+
+// whether computing `expression` with the arguments within `bounds` may overflow, and whether it may not;
+// a division only overflows for the least integer divided by minus one
+fn overflows(expression: &PostfixExpression, bounds: &HashMap<usize, Interval>) -> (bool, bool) {
+    use PostfixArithmetic::*;
+    match expression {
+        PostfixExpression::ARITHMETIC(operator, pre, post) => {
+            let (pre, post) = (interval(pre, bounds), interval(post, bounds));
+            match operator {
+                ADD | SUB | MUL => {
+                    let (low, high) = range(*operator, pre, post);
+                    (low < INTEGERS.0 || high > INTEGERS.1, low <= INTEGERS.1 && high >= INTEGERS.0)
+                },
+                DIV | REM => (pre.0 == INTEGERS.0 && post.0 <= -1 && -1 <= post.1, pre != (INTEGERS.0, INTEGERS.0) || post != (-1, -1)),
+                _ => (false, true),
+            }
+        },
+        PostfixExpression::ARGUMENT(_) | PostfixExpression::INTEGER(_) => (false, true),
+    }
+}
+
+// the comparison of an argument with a constant that a condition amounts to, the argument on the left
+fn constraint(condition: &PostfixCondition) -> Option<(usize, PostfixArithmetic, i64)> {
+    use PostfixArithmetic::*;
+    use PostfixExpression::*;
+    if condition.test == PostfixTest::OVERFLOWS {
+        return None;
+    }
+    let (index, operator, constant) = match &condition.expression {
+        ARITHMETIC(operator, pre, post) => match (pre.as_ref(), post.as_ref()) {
+            (ARGUMENT(index), INTEGER(constant)) => (*index, *operator, *constant),
+            (INTEGER(constant), ARGUMENT(index)) => (*index, match operator {
+                LT => GT, GT => LT, LE => GE, GE => LE, operator => *operator,
+            }, *constant),
+            (_, _) => return None,
+        },
+        ARGUMENT(index) => (*index, NE, 0),
+        INTEGER(_) => return None,
+    };
+    let operator = match (condition.holds, operator) {
+        (true, operator) => operator,
+        (false, LT) => GE, (false, GE) => LT,
+        (false, GT) => LE, (false, LE) => GT,
+        (false, EQ) => NE, (false, NE) => EQ,
+        (false, _) => return None,
+    };
+    match operator {
+        LT | LE | GT | GE | EQ | NE => Some((index, operator, constant as i64)),
+        _ => None,
+    }
+}
+
+// whether some arguments may take every decision of `conditions`, as far as bounds on each argument tell
+fn feasible(conditions: &[PostfixCondition]) -> bool {
+    use PostfixArithmetic::*;
+    let mut bounds: HashMap<usize, Interval> = HashMap::new();
+    conditions.iter().filter_map(constraint).for_each(|(index, operator, constant)| {
+        let (low, high) = bounds.get(&index).copied().unwrap_or(INTEGERS);
+        let narrowed = match operator {
+            LT => (low, high.min(constant - 1)),
+            LE => (low, high.min(constant)),
+            GT => (low.max(constant + 1), high),
+            GE => (low.max(constant), high),
+            EQ => (low.max(constant), high.min(constant)),
+            // only an excluded bound can be narrowed
+            _ => (low + (low == constant) as i64, high - (high == constant) as i64),
+        };
+        bounds.insert(index, narrowed);
+    });
+    bounds.values().all(|(low, high)| low <= high) && conditions.iter().all(|condition| match condition.test {
+        PostfixTest::NONZERO => match (interval(&condition.expression, &bounds), condition.holds) {
+            ((0, 0), true) => false,
+            ((low, high), false) => low <= 0 && 0 <= high,
+            (_, true) => true,
+        },
+        PostfixTest::OVERFLOWS => match (overflows(&condition.expression, &bounds), condition.holds) {
+            ((overflowing, _), true) => overflowing,
+            ((_, fitting), false) => fitting,
+        },
+    })
+}
+
+// the first command outside the core language, anywhere in `commands`, which no path is worked out for
+fn beyond_core(commands: &[PostfixCommand]) -> Option<&PostfixCommand> {
+    commands.iter().find_map(|command| match command {
+        PostfixCommand::INTEGER(_) => None,
+        PostfixCommand::EXECUTE(body) => beyond_core(body),
+        PostfixCommand::SPECIAL(function) if function.extensions().is_empty() && !matches!(function, PostfixFunction::NATIVE(_)) => None,
+        command => Some(command),
+    })
+}
+
+// one way through the decisions of a program: the arguments it is taken for, and what it ends with
+#[derive(Debug,PartialEq)]
+pub struct PostfixPath {
+    pub conditions: Vec<PostfixCondition>,
+    pub outcome: Result<PostfixExpression, PostfixError>,
+}
+
+impl PostfixPath {
+
+    // whether the path is taken for the given arguments, never when some argument it reads is missing
+    pub fn holds(&self, arguments: &[i32]) -> bool {
+        let outcome = self.outcome.as_ref().map_or(0, |expression| expression.arguments());
+        let read = self.conditions.iter().map(|condition| condition.expression.arguments()).fold(outcome, usize::max);
+        read <= arguments.len() && self.conditions.iter().all(|condition| condition.evaluate(arguments))
+    }
+
+    // the first arguments among `values` the path is taken for
+    pub fn witness(&self, paramsize: usize, values: &[i32]) -> Option<Vec<i32>> {
+        argument_vectors(paramsize, values).find(|arguments| self.holds(arguments))
+    }
+}
+
+impl fmt::Display for PostfixPath {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let conditions: Vec<String> = self.conditions.iter().map(|condition| condition.to_string()).collect();
+        match conditions.is_empty() {
+            true => write!(formatter, "always: "),
+            false => write!(formatter, "if {}: ", conditions.join(" and ")),
+        }?;
+        match &self.outcome {
+            Ok(expression) => write!(formatter, "{}", expression),
+            Err(error) => write!(formatter, "{:?}", error),
+        }
+    }
+}
+
+// every vector of `count` arguments taken from `values`, the first argument changing fastest; the vectors
+// are made one at a time, however many there are
+pub fn argument_vectors(count: usize, values: &[i32]) -> impl Iterator<Item = Vec<i32>> + '_ {
+    // indices into `values`, counted up like the digits of a number, lowest first
+    let first = match values.is_empty() && count > 0 {
+        true => None,
+        false => Some(vec![0; count]),
+    };
+    std::iter::successors(first, move |indices: &Vec<usize>| {
+        let mut indices = indices.to_owned();
+        indices.iter_mut().position(|index| {
+            *index = (*index + 1) % values.len();
+            *index != 0
+        }).map(|_| indices)
+    }).map(move |indices| indices.iter().map(|index| values[*index]).collect())
+}
+
+#[derive(Debug,Clone)]
+enum SymbolicValue {
+    EXPRESSION(PostfixExpression),
+    SEQUENCE(Vec<PostfixCommand>),
+}
+
+// the machine state of a path still being followed
+#[derive(Debug,Clone)]
+struct SymbolicState {
+    commands: Vec<PostfixCommand>,
+    stack: Vec<SymbolicValue>,
+    conditions: Vec<PostfixCondition>,
+}
+
+enum Branch {
+    RUNNING(SymbolicState),
+    FINISHED(PostfixPath),
+}
+
+impl SymbolicState {
+
+    fn fail(self, error: PostfixError) -> Vec<Branch> {
+        vec![Branch::FINISHED(PostfixPath { conditions: self.conditions, outcome: Err(error) })]
+    }
+
+    fn continues(self) -> Vec<Branch> {
+        vec![Branch::RUNNING(self)]
+    }
+
+    // whether `expression` passes `test`, when it is a constant or has already been decided
+    fn decided(&self, test: PostfixTest, expression: &PostfixExpression) -> Option<bool> {
+        match (test, expression) {
+            (PostfixTest::NONZERO, PostfixExpression::INTEGER(n)) => Some(*n != 0),
+            (test, expression) => self.conditions.iter().find(|condition| condition.test == test && condition.expression == *expression).map(|condition| condition.holds),
+        }
+    }
+
+    // the state once `expression` is known to pass `test` or not, for each way it can be; a decision
+    // that could not go the other way is left out
+    fn decide(self, test: PostfixTest, expression: PostfixExpression) -> Vec<(bool, SymbolicState)> {
+        match self.decided(test, &expression) {
+            Some(holds) => vec![(holds, self)],
+            None => {
+                let ways: Vec<(bool, SymbolicState)> = [true, false].into_iter().filter_map(|holds| {
+                    let mut state = self.clone();
+                    state.conditions.push(PostfixCondition { expression: expression.clone(), test, holds });
+                    match feasible(&state.conditions) {
+                        true => Some((holds, state)),
+                        false => None,
+                    }
+                }).collect();
+                match ways.as_slice() {
+                    [(holds, _)] => vec![(*holds, self)],
+                    _ => ways,
+                }
+            },
+        }
+    }
+
+    // the state with the result of an arithmetic command on top, or the path ending with an overflow
+    fn compute(self, operator: PostfixArithmetic, result: PostfixExpression) -> Vec<Branch> {
+        self.decide(PostfixTest::OVERFLOWS, result.clone()).into_iter().map(|(overflowing, mut state)| match overflowing {
+            true => Branch::FINISHED(PostfixPath { conditions: state.conditions, outcome: Err(PostfixError::ArithmeticOverflow { function: PostfixFunction::ARITHMETIC(operator) }) }),
+            false => { state.stack.push(SymbolicValue::EXPRESSION(result.clone())); Branch::RUNNING(state) },
+        }).collect()
+    }
+
+    // runs the next command, or ends the path once there is none left
+    fn step(mut self) -> Result<Vec<Branch>, PostfixError> {
+        use PostfixCommand::*;
+        use PostfixFunction::*;
+        use SymbolicValue::*;
+        let command = match self.commands.pop() {
+            Some(command) => command,
+            None => return Ok(vec![Branch::FINISHED(self.finish())]),
+        };
+        let missing = |function, expected_number_of_arguments| PostfixError::WrongNumberOfFunctionArguments { function, expected_number_of_arguments };
+        Ok(match command {
+            INTEGER(n) => { self.stack.push(EXPRESSION(PostfixExpression::INTEGER(n))); self.continues() },
+            EXECUTE(commands) => { self.stack.push(SEQUENCE(commands)); self.continues() },
+            SPECIAL(POP) => match self.stack.pop() {
+                Some(_) => self.continues(),
+                None => self.fail(missing(POP, 1)),
+            },
+            SPECIAL(SWAP) => match (self.stack.pop(), self.stack.pop()) {
+                (Some(above), Some(below)) => { self.stack.push(above); self.stack.push(below); self.continues() },
+                _ => self.fail(missing(SWAP, 2)),
+            },
+            SPECIAL(EXEC) => match self.stack.pop() {
+                Some(SEQUENCE(commands)) => { self.commands.extend(commands.into_iter().rev()); self.continues() },
+                Some(EXPRESSION(_)) => self.fail(PostfixError::WrongTypeOfFunctionArguments { function: EXEC }),
+                None => self.fail(missing(EXEC, 1)),
+            },
+            SPECIAL(NGET) => match self.stack.pop() {
+                Some(EXPRESSION(PostfixExpression::INTEGER(index))) => match usize::try_from(index) {
+                    Ok(depth) if depth >= 1 && depth <= self.stack.len() => match self.stack[self.stack.len() - depth].to_owned() {
+                        EXPRESSION(expression) => { self.stack.push(EXPRESSION(expression)); self.continues() },
+                        SEQUENCE(commands) => self.fail(PostfixError::InvalidValueByNGETFunction { command: EXECUTE(commands) }),
+                    },
+                    _ => { let max = self.stack.len(); self.fail(PostfixError::IndexOutOfRangeByNGETFunction { index, min: 1, max }) },
+                },
+                // a copy from a depth that depends on the arguments
+                Some(EXPRESSION(_)) => return Err(PostfixError::UnsupportedSymbolicCommand(SPECIAL(NGET))),
+                Some(SEQUENCE(_)) => self.fail(PostfixError::WrongTypeOfFunctionArguments { function: NGET }),
+                None => self.fail(missing(NGET, 1)),
+            },
+            SPECIAL(SEL) => match (self.stack.pop(), self.stack.pop(), self.stack.pop()) {
+                (Some(otherwise), Some(then), Some(EXPRESSION(condition))) => self.decide(PostfixTest::NONZERO, condition).into_iter().map(|(holds, mut state)| {
+                    state.stack.push(match holds {
+                        true => then.to_owned(),
+                        false => otherwise.to_owned(),
+                    });
+                    Branch::RUNNING(state)
+                }).collect(),
+                (Some(_), Some(_), Some(SEQUENCE(_))) => self.fail(PostfixError::WrongTypeOfFunctionArguments { function: SEL }),
+                _ => self.fail(missing(SEL, 3)),
+            },
+            SPECIAL(ARITHMETIC(operator)) => match (self.stack.pop(), self.stack.pop()) {
+                (Some(EXPRESSION(PostfixExpression::INTEGER(post))), Some(EXPRESSION(PostfixExpression::INTEGER(pre)))) => {
+                    match apply_function(ARITHMETIC(operator), &[INTEGER(pre), INTEGER(post)], &PostfixConfig::core()) {
+                        Ok(values) => match values.as_slice() {
+                            [INTEGER(n)] => { self.stack.push(EXPRESSION(PostfixExpression::INTEGER(*n))); self.continues() },
+                            _ => self.fail(PostfixError::WrongTypeOfFunctionArguments { function: ARITHMETIC(operator) }),
+                        },
+                        Err(error) => self.fail(error),
+                    }
+                },
+                (Some(EXPRESSION(post)), Some(EXPRESSION(pre))) => {
+                    let result = PostfixExpression::ARITHMETIC(operator, Box::new(pre), Box::new(post.clone()));
+                    match operator {
+                        // dividing takes a path of its own for a divisor of zero
+                        PostfixArithmetic::DIV | PostfixArithmetic::REM => self.decide(PostfixTest::NONZERO, post).into_iter().flat_map(|(holds, state)| match holds {
+                            true => state.compute(operator, result.clone()),
+                            false => vec![Branch::FINISHED(PostfixPath { conditions: state.conditions, outcome: Err(PostfixError::DivideByZero) })],
+                        }).collect(),
+                        // checked arithmetic takes a path of its own for a result beyond an integer
+                        _ => self.compute(operator, result),
+                    }
+                },
+                (Some(_), Some(_)) => self.fail(PostfixError::WrongTypeOfFunctionArguments { function: ARITHMETIC(operator) }),
+                _ => self.fail(missing(ARITHMETIC(operator), 2)),
+            },
+            command => return Err(PostfixError::UnsupportedSymbolicCommand(command)),
+        })
+    }
+
+    fn finish(self) -> PostfixPath {
+        let outcome = match self.stack.last() {
+            Some(SymbolicValue::EXPRESSION(expression)) => Ok(expression.to_owned()),
+            Some(SymbolicValue::SEQUENCE(commands)) => Err(PostfixError::NonNumeralFinalState { command: PostfixCommand::EXECUTE(commands.to_owned()), memory: PostfixMemory::new(0) }),
+            None => Err(PostfixError::EmptyStackFinalState { memory: PostfixMemory::new(0) }),
+        };
+        PostfixPath { conditions: self.conditions, outcome }
+    }
+}
+
+// every path through a core program run on symbolic arguments, in the order the decisions are taken,
+// leaving out those whose decisions contradict each other
+pub fn execute_symbolically(program: &PostfixProgram) -> Result<Vec<PostfixPath>, PostfixError> {
+    if program.variadic {
+        return Err(PostfixError::UnsupportedSymbolicProgram);
+    }
+    if let Some(command) = beyond_core(&program.commands) {
+        return Err(PostfixError::UnsupportedSymbolicCommand(command.to_owned()));
+    }
+    let arguments = (1..=program.paramsize as usize).rev().map(|index| SymbolicValue::EXPRESSION(PostfixExpression::ARGUMENT(index))).collect();
+    let mut pending = vec![Branch::RUNNING(SymbolicState { commands: program.commands.iter().rev().cloned().collect(), stack: arguments, conditions: Vec::new() })];
+    let mut paths = Vec::new();
+    while let Some(branch) = pending.pop() {
+        match branch {
+            Branch::RUNNING(state) => pending.extend(state.step()?.into_iter().rev()),
+            Branch::FINISHED(path) => paths.push(path),
+        }
+    }
+    Ok(paths)
+}
@@ -1,13 +1,41 @@
 use super::errors::PostfixError;
 use paste;
-use super::programs::compile_and_run;
+use super::config::PostfixConfig;
+use super::lexer::parse_code_to_tokens;
+use super::programs::{compile_and_run, compile_and_run_with_config, parse_tokens_to_program};
+use super::types::check_types;
+
+// every program that runs to a result must pass the type checker
+fn well_typed(code: &str, config: &PostfixConfig) -> Result<(), PostfixError> {
+    check_types(&parse_tokens_to_program(parse_code_to_tokens(code)?, config)?, config)
+}
 
 macro_rules! test_case {
     ($( ($name:ident, $code:expr, $arguments:expr, $expected:expr),)*) => ($(
         paste::item! {
             #[test]
             fn [< test_ $name >] () -> Result<(), PostfixError> { 
-                assert_eq!(compile_and_run($code, &$arguments), $expected);
+                let expected = $expected;
+                if expected.is_ok() {
+                    assert_eq!(well_typed($code, &PostfixConfig::default()), Ok(()));
+                }
+                assert_eq!(compile_and_run($code, &$arguments), expected);
+                Ok(())
+            }
+        }
+    )*);
+}
+
+macro_rules! test_case_with_config {
+    ($config:expr; $( ($name:ident, $code:expr, $arguments:expr, $expected:expr),)*) => ($(
+        paste::item! {
+            #[test]
+            fn [< test_ $name >] () -> Result<(), PostfixError> { 
+                let expected = $expected;
+                if expected.is_ok() {
+                    assert_eq!(well_typed($code, &$config), Ok(()));
+                }
+                assert_eq!(compile_and_run_with_config($code, &$arguments, &$config), expected);
                 Ok(())
             }
         }
@@ -18,10 +46,22 @@ macro_rules! test_case {
 mod test_suite {
     use super::*;
     use super::PostfixError::*;
+    use super::super::config::{
+        PostfixConfig,
+        PostfixExtension,
+        PostfixExtension::*,
+        PostfixConversion,
+        PostfixOverflow,
+        PostfixTyping,
+    };
+    use num::rational::Rational64;
     use super::super::enums::{
         PostfixFunction::*,
+        PostfixCommand,
         PostfixCommand::*,
         PostfixArithmetic::*,
+        PostfixMemory,
+        PostfixValueKind,
     };
     
 
@@ -52,7 +92,7 @@ mod test_suite {
         ( quadratic_formular_with_nget, "(postfix 4 4 nget 5 nget mul mul swap 4 nget mul add add)", vec![3, 4, 5, 2], Ok(25) ),
         ( simple_exec, "(postfix 1 (2 mul) exec)", vec![7], Ok(14) ),
         ( negation_routine_with_exec, "(postfix 0 (0 swap sub) 7 swap exec)", vec![], Ok(-7) ),
-        ( non_integer_final_stack_top, "(postfix 0 (2 mul))", vec![], Err(NonNumeralFinalState { command: EXECUTE(vec![INTEGER(2), SPECIAL(ARITHMETIC(MUL))])}) ),
+        ( non_integer_final_stack_top, "(postfix 0 (2 mul))", vec![], Err(NonNumeralFinalState { command: EXECUTE(vec![INTEGER(2), SPECIAL(ARITHMETIC(MUL))]), memory: PostfixMemory::new(0) }) ),
         ( wrong_type_of_arguments_for_arithmetic, "(postfix 0 3 (2 mul) gt)", vec![], Err(WrongTypeOfFunctionArguments { function: ARITHMETIC(GT)}) ),
         ( wrong_type_of_arguments_for_exec, "(postfix 0 3 exec)", vec![], Err(WrongTypeOfFunctionArguments {function: EXEC}) ),
         ( mildly_complicated_exec, "(postfix 0 (7 swap exec) (0 swap sub) swap exec)", vec![], Ok(-7) ),
@@ -65,6 +105,1732 @@ mod test_suite {
         ( sel_exec_second, "(postfix 4 lt (add) (mul) sel exec)", vec![4, 3, 5, 6], Ok(11) ),
         ( absolute_value_neg, "(postfix 1 1 nget 0 lt (0 swap sub) () sel exec)", vec![-7], Ok(7) ),
         ( absolute_value_pos, "(postfix 1 1 nget 0 lt (0 swap sub) () sel exec)", vec![6], Ok(6) ),
+
+        ( definition_of_absolute_value, "(postfix 1 (def abs (1 nget 0 lt (0 swap sub) () sel exec)) abs)", vec![-7], Ok(7) ),
+        ( definition_used_twice, "(postfix 2 (def square (1 nget mul)) square swap square add)", vec![3, 4], Ok(25) ),
+        ( definition_used_in_sequence, "(postfix 1 (def neg (0 swap sub)) (neg) exec)", vec![5], Ok(-5) ),
+        ( definition_using_definition, "(postfix 2 (def not (0 1 sel)) (def nand ((1 0 sel) (0) sel exec not)) nand)", vec![6, 1], Ok(0) ),
+        ( definition_does_not_push, "(postfix 0 (def seven (7)) 1)", vec![], Ok(1) ),
+        ( undefined_name, "(postfix 1 absolute)", vec![-7], Err(InvalidFunctionNameError("absolute".to_string())) ),
+        ( name_used_before_definition, "(postfix 1 absolute (def absolute (1 nget 0 lt (0 swap sub) () sel exec)))", vec![-7], Err(InvalidFunctionNameError("absolute".to_string())) ),
+        ( redefinition, "(postfix 0 (def one (1)) (def one (2)) one)", vec![], Err(Redefinition("one".to_string())) ),
+        ( recursive_definition, "(postfix 1 (def loop (loop)) loop)", vec![1], Err(RecursiveDefinition("loop".to_string())) ),
+        ( definition_of_keyword, "(postfix 0 (def add (1)) 1)", vec![], Err(MalformedDefinition) ),
+        ( definition_without_body, "(postfix 0 (def one 1) one)", vec![], Err(MalformedDefinition) ),
+        ( definition_inside_sequence, "(postfix 0 ((def one (1))) 1)", vec![], Err(MalformedDefinition) ),
     ];
-    
+
+    // the prelude, documented by its test vectors
+    test_case! [
+        ( prelude_not_of_zero, "(postfix 1 not)", vec![0], Ok(1) ),
+        ( prelude_not_of_non_zero, "(postfix 1 not)", vec![6], Ok(0) ),
+        ( prelude_and_true, "(postfix 2 and)", vec![6, 1], Ok(1) ),
+        ( prelude_and_false_top, "(postfix 2 and)", vec![0, 3], Ok(0) ),
+        ( prelude_and_false_below, "(postfix 2 and)", vec![6, 0], Ok(0) ),
+        ( prelude_or_false, "(postfix 2 or)", vec![0, 0], Ok(0) ),
+        ( prelude_or_true_top, "(postfix 2 or)", vec![6, 0], Ok(1) ),
+        ( prelude_or_true_below, "(postfix 2 or)", vec![0, 3], Ok(1) ),
+        ( prelude_neg, "(postfix 1 neg)", vec![5], Ok(-5) ),
+        ( prelude_abs_neg, "(postfix 1 abs)", vec![-7], Ok(7) ),
+        ( prelude_abs_pos, "(postfix 1 abs)", vec![6], Ok(6) ),
+        ( prelude_square, "(postfix 1 square)", vec![-4], Ok(16) ),
+        ( prelude_min_top, "(postfix 2 min)", vec![3, 4], Ok(3) ),
+        ( prelude_min_below, "(postfix 2 min)", vec![4, 3], Ok(3) ),
+        ( prelude_max_top, "(postfix 2 max)", vec![4, 3], Ok(4) ),
+        ( prelude_max_below, "(postfix 2 max)", vec![3, 4], Ok(4) ),
+        ( prelude_le_less, "(postfix 1 4 le)", vec![3], Ok(1) ),
+        ( prelude_le_equal, "(postfix 1 4 le)", vec![4], Ok(1) ),
+        ( prelude_le_greater, "(postfix 1 4 le)", vec![5], Ok(0) ),
+        ( prelude_ge_less, "(postfix 1 4 ge)", vec![3], Ok(0) ),
+        ( prelude_ge_equal, "(postfix 1 4 ge)", vec![4], Ok(1) ),
+        ( prelude_ge_greater, "(postfix 1 4 ge)", vec![5], Ok(1) ),
+        ( prelude_ne_different, "(postfix 1 4 ne)", vec![3], Ok(1) ),
+        ( prelude_ne_equal, "(postfix 1 4 ne)", vec![4], Ok(0) ),
+        ( prelude_across_lines, "(postfix 2\n\tmin\n)", vec![3, 4], Ok(3) ),
+        ( prelude_routine_in_sequence, "(postfix 1 (abs) exec)", vec![-2], Ok(2) ),
+        ( prelude_shadowed_by_program, "(postfix 1 (def abs (1)) abs)", vec![-7], Ok(1) ),
+        ( prelude_expands_to_core, "(postfix 1 (not) 1 nget)", vec![0], Err(InvalidValueByNGETFunction { command: EXECUTE(vec![INTEGER(0), INTEGER(1), SPECIAL(SEL)]) }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().without_prelude();
+        ( prelude_disabled, "(postfix 1 square)", vec![-7], Err(InvalidFunctionNameError("square".to_string())) ),
+        ( prelude_disabled_leaves_math_out_of_dialect, "(postfix 1 abs)", vec![-7], Err(FunctionNotInDialect(ABS)) ),
+        ( prelude_disabled_allows_own_definition, "(postfix 1 (def not (0 1 sel)) not)", vec![0], Ok(1) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig { prelude: Some(0), ..PostfixConfig::core() };
+        ( prelude_unsupported_version, "(postfix 1 abs)", vec![-7], Err(UnsupportedPreludeVersion { requested: 0, available: 1 }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(LOOP);
+        ( repeat_summation, "(postfix 1 0 swap (add) repeat)", vec![10], Ok(55) ),
+        ( repeat_factorial, "(postfix 1 1 swap (mul) repeat)", vec![5], Ok(120) ),
+        ( repeat_counter_on_top, "(postfix 0 0 3 (swap pop) repeat)", vec![], Ok(3) ),
+        ( repeat_zero_times, "(postfix 1 (pop 7) 0 swap repeat)", vec![42], Ok(42) ),
+        ( repeat_nested, "(postfix 0 0 3 (0 swap (add) repeat add) repeat)", vec![], Ok(10) ),
+        ( repeat_negative_count, "(postfix 0 0 1 sub (add) repeat)", vec![], Err(NegativeRepeatCount { count: -1 }) ),
+        ( repeat_wrong_type_of_arguments, "(postfix 0 (add) 3 repeat)", vec![], Err(WrongTypeOfFunctionArguments { function: REPEAT }) ),
+        ( repeat_not_enough_arguments, "(postfix 0 (add) repeat)", vec![], Err(WrongNumberOfFunctionArguments { function: REPEAT, expected_number_of_arguments: 2 }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(MEMORY).with_memory_size(4);
+        ( memory_put_then_get, "(postfix 1 0 put 0 get)", vec![5], Ok(5) ),
+        ( memory_accumulate_through_nested_exec, "(postfix 2 0 put ((0 get add 0 put) exec) exec 0 get)", vec![3, 4], Ok(7) ),
+        ( memory_overwrite_cell, "(postfix 0 1 3 put 2 3 put 3 get)", vec![], Ok(2) ),
+        ( memory_stores_executable_sequence, "(postfix 1 (2 mul) 0 put 0 get exec)", vec![21], Ok(42) ),
+        ( memory_uninitialized_cell, "(postfix 0 1 get)", vec![], Err(UninitializedMemoryCell { index: 1 }) ),
+        ( memory_index_too_large, "(postfix 0 7 4 put)", vec![], Err(IndexOutOfRangeByMemory { index: 4, size: 4 }) ),
+        ( memory_negative_index, "(postfix 0 0 1 sub get)", vec![], Err(IndexOutOfRangeByMemory { index: -1, size: 4 }) ),
+        ( memory_wrong_type_of_index, "(postfix 0 7 (1) put)", vec![], Err(WrongTypeOfFunctionArguments { function: PUT }) ),
+        ( memory_put_not_enough_arguments, "(postfix 0 1 put)", vec![], Err(WrongNumberOfFunctionArguments { function: PUT, expected_number_of_arguments: 2 }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(MEMORY).with_memory_size(0);
+        ( memory_of_size_zero, "(postfix 0 0 get)", vec![], Err(IndexOutOfRangeByMemory { index: 0, size: 0 }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(MEMORY).with_memory_size(2);
+        ( memory_in_empty_final_state, "(postfix 0 5 1 put)", vec![], { let mut memory = PostfixMemory::new(2); memory.put(1, INTEGER(5))?; Err(EmptyStackFinalState { memory }) } ),
+        ( memory_in_non_numeral_final_state, "(postfix 0 5 0 put (1))", vec![], { let mut memory = PostfixMemory::new(2); memory.put(0, INTEGER(5))?; Err(NonNumeralFinalState { command: EXECUTE(vec![INTEGER(1)]), memory }) } ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(EXCEPTION);
+        ( throw_uncaught, "(postfix 0 42 throw)", vec![], Err(Thrown(42)) ),
+        ( try_without_failure, "(postfix 0 (7) (pop 0) try)", vec![], Ok(7) ),
+        ( try_catches_throw, "(postfix 0 (42 throw) (1 add) try)", vec![], Ok(43) ),
+        ( try_catches_throw_from_nested_exec, "(postfix 0 ((3 throw) exec) (1 add) try)", vec![], Ok(4) ),
+        ( try_catches_divide_by_zero, "(postfix 2 (div) () try)", vec![0, 5], Ok(-1) ),
+        ( try_catches_wrong_number_of_arguments, "(postfix 1 (pop pop pop) (pop) try)", vec![9], Ok(9) ),
+        ( try_catches_wrong_type_of_arguments, "(postfix 0 (3 exec) () try)", vec![], Ok(-3) ),
+        ( try_catches_nget_index_out_of_range, "(postfix 0 (5 nget) () try)", vec![], Ok(-4) ),
+        ( try_restores_stack, "(postfix 1 (pop 1 2 3 4 throw) (add) try)", vec![5], Ok(9) ),
+        ( try_nested_rethrow, "(postfix 0 ((1 throw) (1 add throw) try) (10 mul) try)", vec![], Ok(20) ),
+        ( try_does_not_catch_after_body, "(postfix 0 (1) (pop 99) try 0 div)", vec![], Err(DivideByZero) ),
+        ( throw_from_handler, "(postfix 0 (1 throw) (2 throw) try)", vec![], Err(Thrown(2)) ),
+        ( try_empty_body, "(postfix 0 5 () (pop 0) try)", vec![], Ok(5) ),
+        ( try_wrong_type_of_arguments, "(postfix 0 1 (1) try)", vec![], Err(WrongTypeOfFunctionArguments { function: TRY }) ),
+        ( throw_wrong_type_of_arguments, "(postfix 0 (1) throw)", vec![], Err(WrongTypeOfFunctionArguments { function: THROW }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(MATH);
+        ( math_le, "(postfix 1 4 le)", vec![4], Ok(1) ),
+        ( math_ge, "(postfix 1 4 ge)", vec![3], Ok(0) ),
+        ( math_ne, "(postfix 1 4 ne)", vec![3], Ok(1) ),
+        ( math_neg, "(postfix 1 neg)", vec![7], Ok(-7) ),
+        ( math_abs, "(postfix 1 abs)", vec![-7], Ok(7) ),
+        ( math_min, "(postfix 2 min)", vec![3, 4], Ok(3) ),
+        ( math_max, "(postfix 2 max)", vec![3, 4], Ok(4) ),
+        ( math_pow, "(postfix 2 pow)", vec![3, 2], Ok(8) ),
+        ( math_pow_zero, "(postfix 0 0 0 pow)", vec![], Ok(1) ),
+        ( math_bitwise_and, "(postfix 0 12 10 band)", vec![], Ok(8) ),
+        ( math_bitwise_or, "(postfix 0 12 10 bor)", vec![], Ok(14) ),
+        ( math_bitwise_xor, "(postfix 0 12 10 bxor)", vec![], Ok(6) ),
+        ( math_logical_and_from_prelude, "(postfix 2 and)", vec![6, 1], Ok(1) ),
+        ( math_logical_or_from_prelude, "(postfix 2 or)", vec![0, 0], Ok(0) ),
+        ( math_shift_left, "(postfix 0 3 4 shl)", vec![], Ok(48) ),
+        ( math_shift_right, "(postfix 0 0 48 sub 4 shr)", vec![], Ok(-3) ),
+        ( math_builtin_over_prelude, "(postfix 1 (abs) 1 nget)", vec![0], Err(InvalidValueByNGETFunction { command: EXECUTE(vec![SPECIAL(ABS)]) }) ),
+        ( math_prelude_routine_using_builtin, "(postfix 1 square)", vec![-7], Ok(49) ),
+        ( math_builtin_not_definable, "(postfix 1 (def min (1)) min)", vec![7], Err(MalformedDefinition) ),
+        ( math_pow_negative_exponent, "(postfix 1 0 1 sub pow)", vec![2], Err(NegativeExponent { exponent: -1 }) ),
+        ( math_pow_overflow, "(postfix 1 31 pow)", vec![2], Err(ArithmeticOverflow { function: ARITHMETIC(POW) }) ),
+        ( math_neg_overflow, "(postfix 1 neg)", vec![i32::MIN], Err(ArithmeticOverflow { function: NEG }) ),
+        ( math_shift_too_far, "(postfix 0 1 32 shl)", vec![], Err(ArithmeticOverflow { function: ARITHMETIC(SHL) }) ),
+        ( math_missing_operand, "(postfix 1 pow)", vec![2], Err(WrongNumberOfFunctionArguments { function: ARITHMETIC(POW), expected_number_of_arguments: 2 }) ),
+        ( math_sequence_operand, "(postfix 1 () min)", vec![2], Err(WrongTypeOfFunctionArguments { function: ARITHMETIC(MIN) }) ),
+        ( math_rem_by_zero, "(postfix 1 0 rem)", vec![2], Err(DivideByZero) ),
+    ];
+
+    test_case! [
+        ( core_add_overflow, "(postfix 1 1 add)", vec![i32::MAX], Err(ArithmeticOverflow { function: ARITHMETIC(ADD) }) ),
+        ( core_mul_overflow, "(postfix 1 2 mul)", vec![i32::MAX], Err(ArithmeticOverflow { function: ARITHMETIC(MUL) }) ),
+        ( core_div_overflow, "(postfix 2 div)", vec![-1, i32::MIN], Err(ArithmeticOverflow { function: ARITHMETIC(DIV) }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(MATH).with_overflow(PostfixOverflow::WRAPPING);
+        ( wrapping_add, "(postfix 1 1 add)", vec![i32::MAX], Ok(i32::MIN) ),
+        ( wrapping_pow, "(postfix 1 31 pow)", vec![2], Ok(i32::MIN) ),
+        ( wrapping_neg, "(postfix 1 neg)", vec![i32::MIN], Ok(i32::MIN) ),
+        ( wrapping_pow_negative_exponent, "(postfix 1 0 1 sub pow)", vec![2], Err(NegativeExponent { exponent: -1 }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(MATH).with_typing(PostfixTyping::STRICT);
+        ( strict_math_logical_and, "(postfix 2 0 gt swap 0 gt and 1 0 sel)", vec![3, 0], Ok(0) ),
+        ( strict_math_logical_xor, "(postfix 2 0 gt swap 0 gt bxor 1 0 sel)", vec![3, 0], Ok(1) ),
+        ( strict_math_le_is_boolean, "(postfix 1 4 le)", vec![3], Err(NonNumeralFinalState { command: BOOLEAN(true), memory: PostfixMemory::new(0) }) ),
+        ( strict_math_boolean_maximum, "(postfix 0 true false max)", vec![], Err(UnexpectedValueKind { function: ARITHMETIC(MAX), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::BOOLEAN }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(PostfixExtension::RATIONAL);
+        ( rational_decimal_literal, "(postfix 0 1.25 4 mul)", vec![], Ok(5) ),
+        ( rational_exact_division, "(postfix 0 7 2 div 2 mul)", vec![], Ok(7) ),
+        ( rational_whole_division, "(postfix 0 6 2 div 1 nget)", vec![], Ok(3) ),
+        ( rational_decimal_sum, "(postfix 0 0.1 0.2 add 0.3 eq)", vec![], Ok(1) ),
+        ( rational_trailing_zeros, "(postfix 0 2.50 2.5 eq)", vec![], Ok(1) ),
+        ( rational_compared_with_integer, "(postfix 0 1.5 1 lt)", vec![], Ok(0) ),
+        ( rational_copied_by_nget, "(postfix 0 0.5 1 nget add)", vec![], Ok(1) ),
+        ( rational_through_prelude, "(postfix 0 0 2.5 sub abs 2 mul)", vec![], Ok(5) ),
+        ( rational_inexact_result, "(postfix 0 7 2 div)", vec![], Err(UnrepresentableFinalState { value: Rational64::new(7, 2) }) ),
+        ( rational_division_by_zero, "(postfix 0 1.5 0 div)", vec![], Err(DivideByZero) ),
+        ( rational_integer_division_by_zero, "(postfix 0 1 0 div)", vec![], Err(DivideByZero) ),
+        ( rational_remainder, "(postfix 0 1.5 2 rem)", vec![], Err(UnexpectedValueKind { function: ARITHMETIC(REM), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::RATIONAL }) ),
+        ( rational_as_condition, "(postfix 0 0.5 1 2 sel)", vec![], Err(WrongTypeOfFunctionArguments { function: SEL }) ),
+        ( rational_missing_fraction, "(postfix 0 1. 1)", vec![], Err(InvalidDecimalLiteral) ),
+        ( rational_two_points, "(postfix 0 1.2.3)", vec![], Err(InvalidDecimalLiteral) ),
+        ( rational_followed_by_alphabet, "(postfix 0 1.5x)", vec![], Err(InvalidDecimalLiteral) ),
+        ( rational_missing_integer_part, "(postfix 0 .5)", vec![], Err(InvalidCharacterError('.')) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(PostfixExtension::RATIONAL).with_conversion(PostfixConversion::TRUNCATE);
+        ( truncated_result, "(postfix 0 0 7 sub 2 div)", vec![], Ok(-3) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(PostfixExtension::RATIONAL).with_conversion(PostfixConversion::FLOOR);
+        ( floored_result, "(postfix 0 0 7 sub 2 div)", vec![], Ok(-4) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(PostfixExtension::RATIONAL).with_conversion(PostfixConversion::CEIL);
+        ( ceiled_result, "(postfix 0 0 7 sub 2 div)", vec![], Ok(-3) ),
+        ( ceiled_result_out_of_range, "(postfix 1 0.5 add)", vec![i32::MAX], Err(UnrepresentableFinalState { value: Rational64::new(i32::MAX as i64 * 2 + 1, 2) }) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(PostfixExtension::RATIONAL).with_conversion(PostfixConversion::ROUND);
+        ( rounded_half_up, "(postfix 0 7 2 div)", vec![], Ok(4) ),
+        ( rounded_half_down, "(postfix 0 0 7 sub 2 div)", vec![], Ok(-4) ),
+        ( rounded_down, "(postfix 0 1.25)", vec![], Ok(1) ),
+    ];
+
+    test_case! [
+        ( rational_rejected_by_core, "(postfix 0 1.5)", vec![], Err(LiteralNotInDialect(PostfixCommand::RATIONAL(Rational64::new(3, 2)))) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(PostfixExtension::LIST);
+        ( list_length, "(postfix 0 [1 2 3] len)", vec![], Ok(3) ),
+        ( list_empty_length, "(postfix 0 [] len)", vec![], Ok(0) ),
+        ( list_nested_length, "(postfix 0 [[1 2] [] 3] 1 at len)", vec![], Ok(2) ),
+        ( list_at_first, "(postfix 0 [4 5 6] 1 at)", vec![], Ok(4) ),
+        ( list_at_last, "(postfix 0 [4 5 6] 3 at)", vec![], Ok(6) ),
+        ( list_push, "(postfix 1 [] swap push 7 push 2 at)", vec![3], Ok(7) ),
+        ( list_push_sequence, "(postfix 1 [] (2 mul) push 1 at exec)", vec![3], Ok(6) ),
+        ( list_map, "(postfix 0 [1 2 3] (1 nget mul) map 3 at)", vec![], Ok(9) ),
+        ( list_map_empty, "(postfix 0 [] (1 nget mul) map len)", vec![], Ok(0) ),
+        ( list_fold_sum, "(postfix 0 [1 2 3 4] 0 (add) fold)", vec![], Ok(10) ),
+        ( list_fold_empty, "(postfix 0 [] 7 (add) fold)", vec![], Ok(7) ),
+        ( list_fold_into_list, "(postfix 0 [1 2 3] [] (push) fold 2 at)", vec![], Ok(2) ),
+        ( list_copied_by_nget, "(postfix 0 [1 2] 1 nget len swap len add)", vec![], Ok(4) ),
+        ( list_across_lines, "(postfix 0 [1\n2\t3] len)", vec![], Ok(3) ),
+        ( list_final_state, "(postfix 0 [1])", vec![], Err(NonNumeralFinalState { command: PostfixCommand::LIST(vec![INTEGER(1)]), memory: PostfixMemory::new(0) }) ),
+        ( list_at_zero, "(postfix 0 [4 5 6] 0 at)", vec![], Err(IndexOutOfRangeByATFunction { index: 0, min: 1, max: 3 }) ),
+        ( list_at_too_large, "(postfix 0 [4 5 6] 4 at)", vec![], Err(IndexOutOfRangeByATFunction { index: 4, min: 1, max: 3 }) ),
+        ( list_at_empty, "(postfix 0 [] 1 at)", vec![], Err(IndexOutOfRangeByATFunction { index: 1, min: 1, max: 0 }) ),
+        ( list_at_of_integer, "(postfix 0 5 1 at)", vec![], Err(UnexpectedValueKind { function: AT, expected: PostfixValueKind::LIST, actual: PostfixValueKind::INTEGER }) ),
+        ( list_at_sequence_index, "(postfix 0 [1] (1) at)", vec![], Err(UnexpectedValueKind { function: AT, expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::SEQUENCE }) ),
+        ( list_map_without_sequence, "(postfix 0 [1] 2 map)", vec![], Err(UnexpectedValueKind { function: MAP, expected: PostfixValueKind::SEQUENCE, actual: PostfixValueKind::INTEGER }) ),
+        ( list_fold_over_integer, "(postfix 0 1 0 (add) fold)", vec![], Err(UnexpectedValueKind { function: FOLD, expected: PostfixValueKind::LIST, actual: PostfixValueKind::INTEGER }) ),
+        ( list_fold_missing_arguments, "(postfix 0 0 (add) fold)", vec![], Err(WrongNumberOfFunctionArguments { function: FOLD, expected_number_of_arguments: 3 }) ),
+        ( list_map_consuming_too_much, "(postfix 0 [1 2] (pop pop) map)", vec![], Err(WrongNumberOfFunctionArguments { function: POP, expected_number_of_arguments: 1 }) ),
+        ( list_map_keeping_stack_below, "(postfix 1 [1 2] (2 mul) map 2 at add)", vec![3], Ok(7) ),
+        ( list_map_dropping_item, "(postfix 2 [1 2] (pop) map 0 (add) fold)", vec![5, 6], Err(UnbalancedMapBody { expected_stack_height: 3, actual_stack_height: 2 }) ),
+        ( list_map_leaving_two_results, "(postfix 0 [1 2] (1 nget) map)", vec![], Err(UnbalancedMapBody { expected_stack_height: 1, actual_stack_height: 2 }) ),
+        ( list_added_to_integer, "(postfix 0 [1] 1 add)", vec![], Err(WrongTypeOfFunctionArguments { function: ARITHMETIC(ADD) }) ),
+        ( list_with_command, "(postfix 0 [1 add])", vec![], Err(MalformedList) ),
+        ( list_with_sequence, "(postfix 0 [(1)])", vec![], Err(MalformedList) ),
+        ( list_unmatched_bracket, "(postfix 0 1])", vec![], Err(UnmatchedRightBracket) ),
+        ( list_unterminated, "(postfix 0 [1 2)", vec![], Err(UnterminatedList) ),
+    ];
+
+    test_case! [
+        ( list_rejected_by_core, "(postfix 0 [1] 1)", vec![], Err(LiteralNotInDialect(PostfixCommand::LIST(vec![]))) ),
+        ( list_function_rejected_by_core, "(postfix 0 0 (add) fold)", vec![], Err(FunctionNotInDialect(FOLD)) ),
+    ];
+
+    test_case! [
+        ( parameters_instead_of_positions, "(postfix (a b c x) a x x mul mul b x mul add c add)", vec![3, 4, 5, 2], Ok(25) ),
+        ( parameters_same_as_positions, "(postfix 4 4 nget 5 nget mul mul swap 4 nget mul add add)", vec![3, 4, 5, 2], Ok(25) ),
+        ( parameters_last, "(postfix (a b) b)", vec![3, 4], Ok(4) ),
+        ( parameters_none, "(postfix () 1)", vec![], Ok(1) ),
+        ( parameters_above_popped_value, "(postfix (a b) pop b)", vec![3, 4], Ok(4) ),
+        ( parameters_through_definition, "(postfix (a b) (def double (2 mul)) a double b add)", vec![3, 4], Ok(10) ),
+        ( parameters_shadow_prelude, "(postfix (square) square)", vec![-3], Ok(-3) ),
+        ( parameters_counted_as_arguments, "(postfix (a b) a)", vec![1], Err(WrongNumberOfArguments { expected: 2, actual: vec![1] }) ),
+        ( parameters_consumed, "(postfix (a b) add a)", vec![3, 4], Err(ConsumedParameter("a".to_string())) ),
+        ( parameters_swapped, "(postfix (a b) swap a)", vec![3, 4], Err(ConsumedParameter("a".to_string())) ),
+        ( parameters_in_sequence, "(postfix (a) (a) exec)", vec![3], Err(UndeterminedStackDepth("a".to_string())) ),
+        ( parameters_after_exec, "(postfix (a) (1) exec a)", vec![3], Err(UndeterminedStackDepth("a".to_string())) ),
+        ( parameters_duplicated, "(postfix (a a) a)", vec![3, 3], Err(MalformedParameters) ),
+        ( parameters_keyword, "(postfix (add) 1)", vec![3], Err(MalformedParameters) ),
+        ( parameters_unclosed, "(postfix (a b 1)", vec![3, 4], Err(MalformedParameters) ),
+    ];
+
+    test_case! [
+        ( variadic_without_arguments, "(postfix *)", vec![], Ok(0) ),
+        ( variadic_count_on_top, "(postfix *)", vec![5, 6, 7], Ok(3) ),
+        ( variadic_arguments_below_count, "(postfix * pop)", vec![5, 6, 7], Ok(5) ),
+        ( variadic_minimum_met, "(postfix 2+ 3 nget)", vec![5, 6], Ok(6) ),
+        ( variadic_minimum_exceeded, "(postfix 2+)", vec![5, 6, 7], Ok(3) ),
+        ( variadic_minimum_missed, "(postfix 2+)", vec![5], Err(WrongNumberOfArguments { expected: 2, actual: vec![5] }) ),
+        ( variadic_star_in_body, "(postfix 0 *)", vec![], Err(InvalidCharacterError('*')) ),
+        ( variadic_plus_in_body, "(postfix 0 1+)", vec![], Err(InvalidCharacterError('+')) ),
+        ( variadic_plus_without_minimum, "(postfix + 1)", vec![], Err(PostfixShouldBeginWithLeftPostfixAndEndWithRight) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(LOOP);
+        ( variadic_summation_empty, "(postfix * 0 swap (pop add) repeat)", vec![], Ok(0) ),
+        ( variadic_summation, "(postfix * 0 swap (pop add) repeat)", vec![1, 2, 3, 4], Ok(10) ),
+    ];
+
+    test_case! [
+        ( loose_true_is_one, "(postfix 0 true false add)", vec![], Ok(1) ),
+        ( loose_literal_as_condition, "(postfix 0 false 1 2 sel)", vec![], Ok(2) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_typing(PostfixTyping::STRICT);
+        ( strict_comparison_is_boolean, "(postfix 1 4 lt)", vec![3], Err(NonNumeralFinalState { command: BOOLEAN(true), memory: PostfixMemory::new(0) }) ),
+        ( strict_true_is_not_one, "(postfix 1 4 lt 10 add)", vec![3], Err(UnexpectedValueKind { function: ARITHMETIC(ADD), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::BOOLEAN }) ),
+        ( strict_boolean_added_to_boolean, "(postfix 0 true true add)", vec![], Err(UnexpectedValueKind { function: ARITHMETIC(ADD), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::BOOLEAN }) ),
+        ( strict_boolean_ordered, "(postfix 0 true false gt)", vec![], Err(UnexpectedValueKind { function: ARITHMETIC(GT), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::BOOLEAN }) ),
+        ( strict_sel_true, "(postfix 1 4 lt 10 20 sel)", vec![3], Ok(10) ),
+        ( strict_sel_false, "(postfix 1 4 lt 10 20 sel)", vec![5], Ok(20) ),
+        ( strict_true_literal, "(postfix 0 true 1 2 sel)", vec![], Ok(1) ),
+        ( strict_false_literal, "(postfix 0 false 1 2 sel)", vec![], Ok(2) ),
+        ( strict_boolean_equality, "(postfix 0 true false eq 1 2 sel)", vec![], Ok(2) ),
+        ( strict_integer_as_condition, "(postfix 1 2 3 sel)", vec![1], Err(UnexpectedValueKind { function: SEL, expected: PostfixValueKind::BOOLEAN, actual: PostfixValueKind::INTEGER }) ),
+        ( strict_boolean_copied_by_nget, "(postfix 0 true 1 nget and 1 0 sel)", vec![], Ok(1) ),
+        ( strict_prelude_le, "(postfix 2 le 1 0 sel)", vec![4, 3], Ok(1) ),
+        ( strict_prelude_or, "(postfix 2 0 gt swap 0 gt or 1 0 sel)", vec![0, 4], Ok(1) ),
+        ( strict_prelude_abs, "(postfix 1 abs)", vec![-7], Ok(7) ),
+        ( strict_prelude_max, "(postfix 2 max)", vec![3, 7], Ok(7) ),
+    ];
+
+    test_case_with_config! [ PostfixConfig::core().with_extension(PostfixExtension::STRING);
+        ( string_length, r#"(postfix 0 "hi" len)"#, vec![], Ok(2) ),
+        ( string_with_breaking_characters, r#"(postfix 0 "(a b)" len)"#, vec![], Ok(5) ),
+        ( string_escapes, r#"(postfix 0 "a\"b\\c\n" len)"#, vec![], Ok(6) ),
+        ( string_concatenation, r#"(postfix 0 "foo" "bar" concat "foobar" eq)"#, vec![], Ok(1) ),
+        ( string_at_most_through_prelude, r#"(postfix 0 "abc" "abc" le)"#, vec![], Ok(1) ),
+        ( string_less_than, r#"(postfix 0 "abc" "abd" lt)"#, vec![], Ok(1) ),
+        ( string_greater_than, r#"(postfix 0 "abc" "abd" gt)"#, vec![], Ok(0) ),
+        ( string_not_equal, r#"(postfix 0 "abc" "ab" eq)"#, vec![], Ok(0) ),
+        ( string_from_and_to_integer, r#"(postfix 1 str "0" concat int)"#, vec![4], Ok(40) ),
+        ( string_to_negative_integer, r#"(postfix 0 " -12 " int)"#, vec![], Ok(-12) ),
+        ( string_copied_by_nget, r#"(postfix 0 "ab" 1 nget concat len)"#, vec![], Ok(4) ),
+        ( string_final_state, r#"(postfix 0 "x")"#, vec![], Err(NonNumeralFinalState { command: PostfixCommand::STRING("x".to_string()), memory: PostfixMemory::new(0) }) ),
+        ( string_invalid_integer, r#"(postfix 0 "x1" int)"#, vec![], Err(InvalidIntegerString("x1".to_string())) ),
+        ( string_concat_integer, r#"(postfix 0 1 "a" concat)"#, vec![], Err(UnexpectedValueKind { function: CONCAT, expected: PostfixValueKind::STRING, actual: PostfixValueKind::INTEGER }) ),
+        ( string_concat_sequence, r#"(postfix 0 "a" (1) concat)"#, vec![], Err(UnexpectedValueKind { function: CONCAT, expected: PostfixValueKind::STRING, actual: PostfixValueKind::SEQUENCE }) ),
+        ( string_added_to_integer, r#"(postfix 0 "a" 1 add)"#, vec![], Err(UnexpectedValueKind { function: ARITHMETIC(ADD), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::STRING }) ),
+        ( string_multiplied, r#"(postfix 0 "a" "b" mul)"#, vec![], Err(UnexpectedValueKind { function: ARITHMETIC(MUL), expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::STRING }) ),
+        ( string_length_of_integer, "(postfix 0 5 len)", vec![], Err(UnexpectedValueKind { function: LEN, expected: PostfixValueKind::STRING, actual: PostfixValueKind::INTEGER }) ),
+        ( string_of_string, r#"(postfix 0 "5" str)"#, vec![], Err(UnexpectedValueKind { function: STR, expected: PostfixValueKind::INTEGER, actual: PostfixValueKind::STRING }) ),
+        ( string_as_condition, r#"(postfix 0 "a" 1 2 sel)"#, vec![], Err(WrongTypeOfFunctionArguments { function: SEL }) ),
+        ( string_unterminated, r#"(postfix 0 "abc)"#, vec![], Err(UnterminatedString) ),
+        ( string_invalid_escape, r#"(postfix 0 "\q" len)"#, vec![], Err(InvalidEscapeError('q')) ),
+    ];
+
+    test_case! [
+        ( string_rejected_by_core, r#"(postfix 0 "hi" 1)"#, vec![], Err(LiteralNotInDialect(PostfixCommand::STRING("hi".to_string()))) ),
+        ( string_length_rejected_by_core, "(postfix 0 len)", vec![], Err(FunctionNotInDialect(LEN)) ),
+        ( try_rejected_by_core, "(postfix 0 (1) (2) try)", vec![], Err(FunctionNotInDialect(TRY)) ),
+        ( throw_rejected_by_core, "(postfix 0 1 throw)", vec![], Err(FunctionNotInDialect(THROW)) ),
+        ( memory_rejected_by_core, "(postfix 0 0 get)", vec![], Err(FunctionNotInDialect(GET)) ),
+        ( repeat_rejected_by_core, "(postfix 1 0 swap (add) repeat)", vec![10], Err(FunctionNotInDialect(REPEAT)) ),
+    ];
+
+    test_case! [
+        ( comment_to_end_of_line, "; doubles its argument\n(postfix 1 ; the argument\n 2 mul) ; done", vec![4], Ok(8) ),
+        ( comment_breaks_token, "(postfix 0 12;34\n)", vec![], Ok(12) ),
+        ( comment_closing_parenthesis, "(postfix 0 1 ; )", vec![], Err(PostfixShouldBeginWithLeftPostfixAndEndWithRight) ),
+    ];
+
+}
+
+#[cfg(test)]
+mod lexer_suite {
+    use super::super::lexer::{
+        parse_code_to_tokens_and_comments,
+        Position,
+    };
+
+    #[test]
+    fn test_positions_of_tokens_and_comments() {
+        let (tokens, comments) = parse_code_to_tokens_and_comments("(postfix 1 ; first\n  12 \"a b\"(x)3.5)").unwrap();
+        let at = |line, column| Position { line, column };
+        assert_eq!(tokens.iter().map(|(_, position)| *position).collect::<Vec<_>>(), vec![
+            at(1, 1), at(1, 2), at(1, 10), at(2, 3), at(2, 6), at(2, 11), at(2, 12), at(2, 13), at(2, 14), at(2, 17),
+        ]);
+        assert_eq!(comments, vec![("first".to_string(), at(1, 12))]);
+    }
+}
+
+#[cfg(test)]
+mod loop_suite {
+    use super::super::config::{
+        PostfixConfig,
+        PostfixExtension,
+    };
+    use super::super::enums::{
+        execute_command,
+        PostfixArithmetic::{
+            ADD,
+            MUL,
+        },
+        PostfixCommand,
+        PostfixCommand::*,
+        PostfixContext,
+        PostfixFunction,
+        PostfixFunction::*,
+        PostfixMemory,
+        PostfixState,
+    };
+    use super::super::io::StandardIo;
+
+    #[test]
+    fn test_repeat_holds_one_iteration_at_a_time() {
+        let config = PostfixConfig::core().with_extension(PostfixExtension::LOOP);
+        let mut state = PostfixState {
+            commands: Vec::new(),
+            stack: vec![INTEGER(0), INTEGER(1_000_000_000), EXECUTE(vec![SPECIAL(ARITHMETIC(ADD))])],
+            memory: PostfixMemory::new(0),
+            handlers: Vec::new(),
+        };
+        let mut context = PostfixContext { config: &config, io: &mut StandardIo };
+        execute_command(&SPECIAL(REPEAT), &mut state, &mut context).unwrap();
+        let mut longest = state.commands.len();
+        // twenty iterations of five steps each
+        (0..100).for_each(|_| {
+            let command = state.commands.pop().unwrap();
+            execute_command(&command, &mut state, &mut context).unwrap();
+            longest = longest.max(state.commands.len());
+        });
+        assert!(longest <= 5);
+        assert_eq!(state.stack, vec![INTEGER(210)]);
+    }
+
+    // commands pending over a whole run of `function`, applied to `stack`
+    fn longest_pending(function: PostfixFunction, stack: Vec<PostfixCommand>) -> (usize, Vec<PostfixCommand>) {
+        let config = PostfixConfig::core().with_extension(PostfixExtension::LIST);
+        let mut state = PostfixState { commands: vec![SPECIAL(function)], stack, memory: PostfixMemory::new(0), handlers: Vec::new() };
+        let mut context = PostfixContext { config: &config, io: &mut StandardIo };
+        let mut longest = 0;
+        while let Some(command) = state.commands.pop() {
+            execute_command(&command, &mut state, &mut context).unwrap();
+            longest = longest.max(state.commands.len());
+        }
+        (longest, state.stack)
+    }
+
+    #[test]
+    fn test_map_and_fold_hold_one_item_at_a_time() {
+        let items = vec![INTEGER(1); 1000];
+        let body = EXECUTE(vec![INTEGER(2), SPECIAL(ARITHMETIC(MUL))]);
+        let (longest, stack) = longest_pending(MAP, vec![LIST(items.clone()), body]);
+        assert!(longest <= 7);
+        assert_eq!(stack, vec![LIST(vec![INTEGER(2); 1000])]);
+        let (longest, stack) = longest_pending(FOLD, vec![LIST(items), INTEGER(0), EXECUTE(vec![SPECIAL(ARITHMETIC(ADD))])]);
+        assert!(longest <= 5);
+        assert_eq!(stack, vec![INTEGER(1000)]);
+    }
+}
+
+// in-memory file system standing in for the disk when resolving imports
+#[cfg(test)]
+#[derive(Debug,Default)]
+pub struct InMemoryLoader {
+    files: std::collections::HashMap<std::path::PathBuf, String>,
+}
+
+#[cfg(test)]
+impl InMemoryLoader {
+    pub fn with_file(mut self, path: &str, code: &str) -> Self {
+        self.files.insert(super::modules::without_current_directory(std::path::Path::new(path)), code.to_string());
+        self
+    }
+}
+
+#[cfg(test)]
+impl super::modules::ModuleLoader for InMemoryLoader {
+    fn read(&self, path: &std::path::Path) -> Option<String> {
+        self.files.get(&self.resolve(path)).cloned()
+    }
+}
+
+#[cfg(test)]
+mod module_suite {
+    use std::path::{Path, PathBuf};
+    use super::*;
+    use super::PostfixError::*;
+    use super::super::{
+        config::PostfixConfig,
+        enums::PostfixSource::*,
+        lexer::Position,
+        modules::{
+            FileSystemLoader,
+            ModuleLoader,
+        },
+        programs::compile_file_and_run,
+    };
+
+    fn run(loader: &InMemoryLoader, config: &PostfixConfig, arguments: Vec<i32>) -> Result<i32, PostfixError> {
+        compile_file_and_run(Path::new("lib/main.postfix"), &arguments, config, loader, &mut ScriptedIo::default())
+    }
+
+    #[test]
+    fn test_import_relative_to_importing_file() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 1 (import util) double)")
+            .with_file("lib/util.postfix", "(def double (2 mul))");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![4]), Ok(8));
+    }
+
+    #[test]
+    fn test_import_name_with_digits() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 1 (import ex10) times2)")
+            .with_file("lib/ex10.postfix", "(def times2 (2 mul))");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![4]), Ok(8));
+    }
+
+    #[test]
+    fn test_import_from_search_path() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 1 (import numbers) cube)")
+            .with_file("std/numbers.postfix", "(def cube (1 nget 1 nget mul mul))");
+        assert_eq!(run(&loader, &PostfixConfig::core().with_search_path("std"), vec![3]), Ok(27));
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![3]), Err(ModuleNotFound {
+            name: "numbers".to_string(),
+            searched: vec![PathBuf::from("lib/numbers.postfix")],
+        }));
+    }
+
+    #[test]
+    fn test_import_prefers_importing_directory() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 0 (import numbers) one)")
+            .with_file("lib/numbers.postfix", "(def one (1))")
+            .with_file("std/numbers.postfix", "(def one (2))");
+        assert_eq!(run(&loader, &PostfixConfig::core().with_search_path("std"), vec![]), Ok(1));
+    }
+
+    #[test]
+    fn test_import_transitively_and_once() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 1 (import left) (import right) twice quadruple)")
+            .with_file("lib/left.postfix", "(import base) (def twice (double))")
+            .with_file("lib/right.postfix", "(import base) (def quadruple (double double))")
+            .with_file("lib/base.postfix", "(def double (2 mul))");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![1]), Ok(8));
+    }
+
+    #[test]
+    fn test_import_cycle() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 0 (import even) 0)")
+            .with_file("lib/even.postfix", "(import odd)")
+            .with_file("lib/odd.postfix", "(import even)");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![]), Err(ImportCycle(vec![
+            PathBuf::from("lib/main.postfix"),
+            PathBuf::from("lib/even.postfix"),
+            PathBuf::from("lib/odd.postfix"),
+            PathBuf::from("lib/even.postfix"),
+        ])));
+    }
+
+    #[test]
+    fn test_import_of_importing_file_is_a_cycle() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 0 (import main) 0)");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![]), Err(ImportCycle(vec![
+            PathBuf::from("lib/main.postfix"),
+            PathBuf::from("lib/main.postfix"),
+        ])));
+    }
+
+    #[test]
+    fn test_duplicate_definition_cites_both_sources() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 1 (import util)\n  (def double (2 mul)) double)")
+            .with_file("lib/util.postfix", "(def double (1 nget add))");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![4]), Err(DuplicateDefinition {
+            name: "double".to_string(),
+            first: (FILE(PathBuf::from("lib/util.postfix")), Position { line: 1, column: 6 }),
+            second: (FILE(PathBuf::from("lib/main.postfix")), Position { line: 2, column: 8 }),
+        }));
+    }
+
+    #[test]
+    fn test_module_reached_through_two_spellings_is_imported_once() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 1 (import left) (import right) twice)")
+            .with_file("lib/left.postfix", "(def twice (2 mul))")
+            .with_file("std/right.postfix", "(import left)");
+        // left is found next to main as lib/left.postfix, then from right along the search path as ./lib/left.postfix
+        assert_eq!(run(&loader, &PostfixConfig::core().with_search_path("std").with_search_path("./lib"), vec![4]), Ok(8));
+    }
+
+    #[test]
+    fn test_file_system_paths_resolved() {
+        let loader = FileSystemLoader;
+        assert_eq!(loader.resolve(Path::new("./code/postfix/ex1.postfix")), loader.resolve(Path::new("code/postfix/lib/../ex1.postfix")));
+        assert_eq!(loader.resolve(Path::new("./missing/./file.postfix")), PathBuf::from("missing/file.postfix"));
+    }
+
+    #[test]
+    fn test_module_may_shadow_prelude() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 1 (import util) abs)")
+            .with_file("lib/util.postfix", "(def abs (pop 0))");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![-4]), Ok(0));
+    }
+
+    #[test]
+    fn test_module_with_commands() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 0 (import util) 1)")
+            .with_file("lib/util.postfix", "(def one (1)) 2");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![]), Err(OnlyDefinitionsAllowed(FILE(PathBuf::from("lib/util.postfix")))));
+    }
+
+    #[test]
+    fn test_import_with_two_names() {
+        let loader = InMemoryLoader::default()
+            .with_file("lib/main.postfix", "(postfix 0 (import left right) 1)")
+            .with_file("lib/left.postfix", "")
+            .with_file("lib/right.postfix", "");
+        assert_eq!(run(&loader, &PostfixConfig::core(), vec![]), Err(MalformedImport));
+    }
+
+    #[test]
+    fn test_missing_source() {
+        assert_eq!(run(&InMemoryLoader::default(), &PostfixConfig::core(), vec![]), Err(SourceNotFound(PathBuf::from("lib/main.postfix"))));
+    }
+
+    #[test]
+    fn test_import_from_inline_program() -> Result<(), PostfixError> {
+        use super::super::{
+            lexer::parse_code_to_tokens,
+            programs::parse_tokens_to_program_with_loader,
+        };
+        let loader = InMemoryLoader::default().with_file("util.postfix", "(def seven (7))");
+        let tokens = parse_code_to_tokens("(postfix 0 (import util) seven)")?;
+        let program = parse_tokens_to_program_with_loader(tokens, &PostfixConfig::core(), None, &loader)?;
+        assert_eq!(program.commands, vec![super::super::enums::PostfixCommand::INTEGER(7)]);
+        Ok(())
+    }
+
+    test_case! [
+        ( import_without_name, "(postfix 0 (import) 1)", vec![], Err(MalformedImport) ),
+        ( import_inside_sequence, "(postfix 0 ((import a)) 1)", vec![], Err(MalformedImport) ),
+    ];
+}
+
+// scripted input queue and captured output buffer standing in for the host
+#[cfg(test)]
+#[derive(Debug,Default)]
+pub struct ScriptedIo {
+    pub input: std::collections::VecDeque<i32>,
+    pub output: String,
+}
+
+#[cfg(test)]
+impl ScriptedIo {
+    pub fn with_input(input: Vec<i32>) -> Self {
+        Self { input: input.into(), output: String::new() }
+    }
+}
+
+#[cfg(test)]
+impl super::io::HostIo for ScriptedIo {
+    fn read(&mut self) -> Option<i32> {
+        self.input.pop_front()
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod io_suite {
+    use super::*;
+    use super::PostfixError::*;
+    use super::super::{
+        config::{
+            PostfixConfig,
+            PostfixExtension,
+            PostfixExtension::*,
+        },
+        enums::PostfixFunction::*,
+        programs::{
+            compile_and_run_with_io,
+            compile_file_and_run,
+        },
+    };
+
+    fn run(code: &str, arguments: Vec<i32>, input: Vec<i32>) -> (Result<i32, PostfixError>, String) {
+        let mut io = ScriptedIo::with_input(input);
+        let result = compile_and_run_with_io(code, &arguments, &PostfixConfig::core().with_extension(IO), &mut io);
+        (result, io.output)
+    }
+
+    #[test]
+    fn test_print() {
+        assert_eq!(run("(postfix 2 1 nget print add)", vec![3, 4], vec![]), (Ok(7), "3\n".to_string()));
+    }
+
+    #[test]
+    fn test_print_labeled_string() {
+        let mut io = ScriptedIo::default();
+        let config = PostfixConfig::core().with_extension(IO).with_extension(PostfixExtension::STRING);
+        let result = compile_and_run_with_io(r#"(postfix 2 add "sum: " 2 nget str concat print)"#, &vec![3, 4], &config, &mut io);
+        assert_eq!((result, io.output), (Ok(7), "sum: 7\n".to_string()));
+    }
+
+    #[test]
+    fn test_file_through_host() {
+        let loader = InMemoryLoader::default().with_file("echo.postfix", "(postfix 0 read 1 nget print)");
+        let mut io = ScriptedIo::with_input(vec![5]);
+        let result = compile_file_and_run(std::path::Path::new("echo.postfix"), &vec![], &PostfixConfig::core().with_extension(IO), &loader, &mut io);
+        assert_eq!((result, io.output), (Ok(5), "5\n".to_string()));
+    }
+
+    #[test]
+    fn test_read() {
+        assert_eq!(run("(postfix 0 read read sub)", vec![], vec![3, 4]), (Ok(-1), String::new()));
+    }
+
+    #[test]
+    fn test_emit() {
+        assert_eq!(run("(postfix 0 72 emit 105 emit 10 emit 0)", vec![], vec![]), (Ok(0), "Hi\n".to_string()));
+    }
+
+    #[test]
+    fn test_labeled_output_through_sequence() {
+        assert_eq!(
+            run("(postfix 0 read read mul (120 emit 61 emit 1 nget print) exec)", vec![], vec![6, 7]),
+            (Ok(42), "x=42\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_output_kept_before_failure() {
+        assert_eq!(run("(postfix 0 1 print 1 0 div)", vec![], vec![]), (Err(DivideByZero), "1\n".to_string()));
+    }
+
+    #[test]
+    fn test_end_of_input() {
+        assert_eq!(run("(postfix 0 read read add)", vec![], vec![1]), (Err(EndOfInput), String::new()));
+    }
+
+    #[test]
+    fn test_invalid_character_code() {
+        assert_eq!(run("(postfix 0 0 1 sub emit)", vec![], vec![]), (Err(InvalidCharacterCode(-1)), String::new()));
+    }
+
+    #[test]
+    fn test_print_wrong_type_of_arguments() {
+        assert_eq!(run("(postfix 0 (1) print)", vec![], vec![]), (Err(WrongTypeOfFunctionArguments { function: PRINT }), String::new()));
+    }
+
+    test_case! [
+        ( print_rejected_by_core, "(postfix 1 1 nget print)", vec![3], Err(FunctionNotInDialect(PRINT)) ),
+        ( read_rejected_by_core, "(postfix 0 read)", vec![], Err(FunctionNotInDialect(READ)) ),
+        ( emit_rejected_by_core, "(postfix 0 65 emit 0)", vec![], Err(FunctionNotInDialect(EMIT)) ),
+    ];
+}
+
+#[cfg(test)]
+mod native_suite {
+    use super::*;
+    use super::PostfixError::*;
+    use super::super::{
+        config::PostfixConfig,
+        natives::NativeRegistry,
+    };
+
+    fn gcd(a: i32, b: i32) -> i32 {
+        match b {
+            0 => a.abs(),
+            b => gcd(b, a % b),
+        }
+    }
+
+    fn natives() -> Result<NativeRegistry, PostfixError> {
+        NativeRegistry::default()
+            .register("gcd", 2, |arguments| Ok(vec![gcd(arguments[0], arguments[1])]))?
+            .register("divmod", 2, |arguments| match arguments[1] {
+                0 => Err("division by zero".to_string()),
+                d => Ok(vec![arguments[0] / d, arguments[0] % d]),
+            })?
+            .register("seven", 0, |_| Ok(vec![7]))
+    }
+
+    fn native_config() -> PostfixConfig {
+        PostfixConfig::core().with_natives(natives().unwrap())
+    }
+
+    test_case_with_config! [ native_config();
+        ( native_gcd, "(postfix 2 gcd)", vec![12, 18], Ok(6) ),
+        ( native_arguments_deepest_first, "(postfix 0 7 2 divmod pop)", vec![], Ok(3) ),
+        ( native_multiple_results, "(postfix 0 7 2 divmod)", vec![], Ok(1) ),
+        ( native_without_arguments_in_sequence, "(postfix 0 (seven) exec)", vec![], Ok(7) ),
+        ( native_used_in_definition, "(postfix 2 (def coprime (gcd 1 eq)) coprime)", vec![9, 4], Ok(1) ),
+        ( native_shadowed_by_definition, "(postfix 0 (def seven (8)) seven)", vec![], Ok(8) ),
+        ( native_not_enough_arguments, "(postfix 1 gcd)", vec![12], Err(WrongNumberOfNativeArguments { name: "gcd".to_string(), expected_number_of_arguments: 2 }) ),
+        ( native_wrong_type_of_arguments, "(postfix 1 (1) gcd)", vec![12], Err(WrongTypeOfNativeArguments { name: "gcd".to_string() }) ),
+        ( native_failure, "(postfix 0 1 0 divmod)", vec![], Err(NativeFunctionFailure { name: "divmod".to_string(), message: "division by zero".to_string() }) ),
+    ];
+
+    test_case! [
+        ( native_not_registered, "(postfix 2 gcd)", vec![12, 18], Err(InvalidFunctionNameError("gcd".to_string())) ),
+    ];
+
+    #[test]
+    fn test_register_keyword() {
+        assert_eq!(NativeRegistry::default().register("add", 2, |_| Ok(vec![])).err(), Some(InvalidNativeName("add".to_string())));
+    }
+
+    #[test]
+    fn test_register_invalid_name() {
+        assert_eq!(NativeRegistry::default().register("2x", 1, |_| Ok(vec![])).err(), Some(InvalidNativeName("2x".to_string())));
+        assert_eq!(NativeRegistry::default().register("two words", 1, |_| Ok(vec![])).err(), Some(InvalidNativeName("two words".to_string())));
+    }
+
+    #[test]
+    fn test_register_prelude_routine() {
+        assert_eq!(NativeRegistry::default().register("square", 1, |_| Ok(vec![])).err(), Some(Redefinition("square".to_string())));
+        assert_eq!(NativeRegistry::default().register("not", 1, |_| Ok(vec![])).err(), Some(Redefinition("not".to_string())));
+    }
+
+    #[test]
+    fn test_register_twice() {
+        assert_eq!(natives().and_then(|natives| natives.register("gcd", 1, |_| Ok(vec![]))).err(), Some(Redefinition("gcd".to_string())));
+    }
+}
+
+#[cfg(test)]
+mod effects_suite {
+    use super::*;
+    use super::PostfixError::*;
+    use super::super::{
+        config::PostfixConfig,
+        effects::{
+            check_program,
+            sequence_effect,
+            StackEffect,
+        },
+        enums::{
+            PostfixArithmetic::*,
+            PostfixFunction::*,
+        },
+        lexer::parse_code_to_tokens,
+        programs::parse_tokens_to_program,
+    };
+
+    fn effect(code: &str) -> Option<StackEffect> {
+        let program = parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), &PostfixConfig::core()).unwrap();
+        sequence_effect(&program.commands)
+    }
+
+    fn check(code: &str) -> Result<(), PostfixError> {
+        check_program(&parse_tokens_to_program(parse_code_to_tokens(code)?, &PostfixConfig::core())?)
+    }
+
+    #[test]
+    fn test_effect_of_commands() {
+        assert_eq!(effect("(postfix 0 add)"), Some(StackEffect { inputs: 2, outputs: 1 }));
+        assert_eq!(effect("(postfix 0 swap)"), Some(StackEffect { inputs: 2, outputs: 2 }));
+        assert_eq!(effect("(postfix 0 pop pop)"), Some(StackEffect { inputs: 2, outputs: 0 }));
+        assert_eq!(effect("(postfix 0 1 2)"), Some(StackEffect { inputs: 0, outputs: 2 }));
+        assert_eq!(effect("(postfix 0 4 mul add)"), Some(StackEffect { inputs: 2, outputs: 1 }));
+        assert_eq!(effect("(postfix 0 3 sel)"), Some(StackEffect { inputs: 2, outputs: 1 }));
+    }
+
+    #[test]
+    fn test_effect_through_exec() {
+        assert_eq!(effect("(postfix 0 (1 nget mul) exec)"), Some(StackEffect { inputs: 1, outputs: 1 }));
+        assert_eq!(effect("(postfix 0 1 (add) swap pop exec)"), Some(StackEffect { inputs: 2, outputs: 1 }));
+        assert_eq!(effect("(postfix 0 (add) (mul) sel exec)"), Some(StackEffect { inputs: 3, outputs: 1 }));
+        assert_eq!(effect("(postfix 0 abs)"), Some(StackEffect { inputs: 1, outputs: 1 }));
+    }
+
+    #[test]
+    fn test_effect_unknown() {
+        assert_eq!(effect("(postfix 0 exec)"), None);
+        assert_eq!(effect("(postfix 0 (add) (pop) sel exec)"), None);
+        assert_eq!(effect("(postfix 0 (1 nget) 1 nget exec)"), None);
+    }
+
+    #[test]
+    fn test_effect_of_deep_copy() {
+        // values reached far down are counted, not built one by one
+        assert_eq!(effect("(postfix 0 2000000000 nget)"), Some(StackEffect { inputs: 2000000000, outputs: 2000000001 }));
+        assert_eq!(effect("(postfix 0 2000000000 nget 2000000000 nget pop)"), Some(StackEffect { inputs: 2000000000, outputs: 2000000001 }));
+        assert_eq!(check("(postfix 0 2000000000 nget)"), Ok(()));
+        assert_eq!(check("(postfix 2000000000 2000000000 nget)"), Ok(()));
+    }
+
+    #[test]
+    fn test_check_accepts() {
+        assert_eq!(check("(postfix 2 add)"), Ok(()));
+        assert_eq!(check("(postfix 1 1 nget 0 lt (0 swap sub) () sel exec)"), Ok(()));
+        assert_eq!(check("(postfix 2 (1 nget) (0) sel exec)"), Ok(()));
+        assert_eq!(check("(postfix * add)"), Ok(()));
+    }
+
+    #[test]
+    fn test_check_rejects_underflow() {
+        assert_eq!(check("(postfix 1 4 mul add)"), Err(StackUnderflow { function: ARITHMETIC(ADD), expected_number_of_arguments: 2, available: 1 }));
+        assert_eq!(check("(postfix 0 pop)"), Err(StackUnderflow { function: POP, expected_number_of_arguments: 1, available: 0 }));
+        assert_eq!(check("(postfix 1 (add) exec)"), Err(StackUnderflow { function: ARITHMETIC(ADD), expected_number_of_arguments: 2, available: 1 }));
+        assert_eq!(check("(postfix 1 (add) (mul) sel exec)"), Err(StackUnderflow { function: ARITHMETIC(ADD), expected_number_of_arguments: 2, available: 0 }));
+        assert_eq!(check("(postfix 0 (pop) exec 0)"), Err(StackUnderflow { function: POP, expected_number_of_arguments: 1, available: 0 }));
+    }
+
+    #[test]
+    fn test_check_leaves_paths_to_run_time() {
+        // one branch underflows, the other may run fine
+        assert_eq!(check("(postfix 2 (add) (pop) sel exec)"), Ok(()));
+        // the count of arguments given to a variadic program is not known
+        assert_eq!(check("(postfix 1+ pop pop pop)"), Ok(()));
+    }
+
+    test_case_with_config! [ PostfixConfig::core().with_stack_check();
+        ( checked_program_runs, "(postfix 2 add)", vec![3, 4], Ok(7) ),
+        ( checked_program_underflows, "(postfix 1 4 mul add)", vec![3], Err(StackUnderflow { function: ARITHMETIC(ADD), expected_number_of_arguments: 2, available: 1 }) ),
+    ];
+}
+
+#[cfg(test)]
+mod types_suite {
+    use super::*;
+    use super::PostfixError::*;
+    use super::super::{
+        config::{
+            PostfixExtension,
+            PostfixTyping,
+        },
+        effects::StackEffect,
+        lexer::Position,
+        enums::{
+            PostfixArithmetic::*,
+            PostfixFunction,
+            PostfixFunction::*,
+            PostfixValueKind,
+        },
+        types::{
+            infer_types,
+            PostfixType,
+        },
+    };
+
+    fn types(code: &str) -> Result<Option<Vec<PostfixType>>, PostfixError> {
+        infer_types(&parse_tokens_to_program(parse_code_to_tokens(code)?, &PostfixConfig::core())?, &PostfixConfig::core())
+    }
+
+    fn mistyped(function: PostfixFunction, location: Vec<usize>, (line, column): (u32, u32), expected: PostfixValueKind, actual: PostfixValueKind) -> Result<(), PostfixError> {
+        Err(MistypedArgument { function, location, position: Some(Position { line, column }), expected, actual })
+    }
+
+    #[test]
+    fn test_infer_types() {
+        assert_eq!(types("(postfix 2 add)"), Ok(Some(vec![PostfixType::INTEGER])));
+        assert_eq!(types("(postfix 0 (1 nget mul))"), Ok(Some(vec![PostfixType::SEQUENCE(Some(StackEffect { inputs: 1, outputs: 1 }))])));
+        assert_eq!(types("(postfix 0 (2000000000 nget pop))"), Ok(Some(vec![PostfixType::SEQUENCE(Some(StackEffect { inputs: 2000000000, outputs: 2000000000 }))])));
+        assert_eq!(types("(postfix 1 (add) (mul) sel)"), Ok(Some(vec![PostfixType::SEQUENCE(Some(StackEffect { inputs: 2, outputs: 1 }))])));
+        assert_eq!(types("(postfix 1 (add) (pop) sel)"), Ok(Some(vec![PostfixType::SEQUENCE(None)])));
+        assert_eq!(types("(postfix 1 (add) 2 sel)"), Ok(Some(vec![PostfixType::UNKNOWN])));
+        assert_eq!(types("(postfix 1 5 (1 nget mul) exec)"), Ok(Some(vec![PostfixType::INTEGER, PostfixType::INTEGER])));
+        assert_eq!(types("(postfix 1 2 nget)"), Ok(None));
+    }
+
+    #[test]
+    fn test_check_rejects_definite_errors() {
+        assert_eq!(well_typed("(postfix 0 3 exec)", &PostfixConfig::core()), mistyped(EXEC, vec![1], (1, 14), PostfixValueKind::SEQUENCE, PostfixValueKind::INTEGER));
+        assert_eq!(well_typed("(postfix 1 (2) add)", &PostfixConfig::core()), mistyped(ARITHMETIC(ADD), vec![1], (1, 16), PostfixValueKind::INTEGER, PostfixValueKind::SEQUENCE));
+        assert_eq!(well_typed("(postfix 0 (2 mul) 3 4 sel)", &PostfixConfig::core()), mistyped(SEL, vec![3], (1, 24), PostfixValueKind::BOOLEAN, PostfixValueKind::SEQUENCE));
+        assert_eq!(well_typed("(postfix 1 (2 (3) mul) exec)", &PostfixConfig::core()), mistyped(ARITHMETIC(MUL), vec![0, 2], (1, 19), PostfixValueKind::INTEGER, PostfixValueKind::SEQUENCE));
+        assert_eq!(well_typed("(postfix 1 (3 exec) (4 exec) sel exec)", &PostfixConfig::core()), mistyped(EXEC, vec![0, 1], (1, 15), PostfixValueKind::SEQUENCE, PostfixValueKind::INTEGER));
+        assert_eq!(well_typed("(postfix 0 (add) 3 repeat)", &PostfixConfig::core().with_extension(PostfixExtension::LOOP)), mistyped(REPEAT, vec![2], (1, 20), PostfixValueKind::SEQUENCE, PostfixValueKind::INTEGER));
+    }
+
+    #[test]
+    fn test_check_locates_errors_in_source() {
+        assert_eq!(well_typed("(postfix 0\n  3\n  exec)", &PostfixConfig::core()), mistyped(EXEC, vec![1], (3, 3), PostfixValueKind::SEQUENCE, PostfixValueKind::INTEGER));
+        // commands spliced in from a definition are written where it is used
+        assert_eq!(well_typed("(postfix 0 (def bad (3 exec))\n  bad)", &PostfixConfig::core()), mistyped(EXEC, vec![1], (2, 3), PostfixValueKind::SEQUENCE, PostfixValueKind::INTEGER));
+        assert_eq!(well_typed("(postfix 0 (def run ((3 exec) exec))\n  run)", &PostfixConfig::core()), mistyped(EXEC, vec![0, 1], (2, 3), PostfixValueKind::SEQUENCE, PostfixValueKind::INTEGER));
+        assert_eq!(well_typed("(postfix 0 (def run (3 exec))\n  (run) exec)", &PostfixConfig::core()), mistyped(EXEC, vec![0, 1], (2, 4), PostfixValueKind::SEQUENCE, PostfixValueKind::INTEGER));
+    }
+
+    #[test]
+    fn test_check_follows_the_dialect() {
+        let strict = PostfixConfig::core().with_typing(PostfixTyping::STRICT);
+        assert_eq!(well_typed("(postfix 1 1 2 sel)", &PostfixConfig::core()), Ok(()));
+        assert_eq!(well_typed("(postfix 1 1 2 sel)", &strict), mistyped(SEL, vec![2], (1, 16), PostfixValueKind::BOOLEAN, PostfixValueKind::INTEGER));
+        assert_eq!(well_typed("(postfix 1 0 lt 1 2 sel)", &strict), Ok(()));
+        let list = PostfixConfig::core().with_extension(PostfixExtension::LIST);
+        assert_eq!(well_typed("(postfix 0 3 (1) map)", &list), mistyped(MAP, vec![2], (1, 18), PostfixValueKind::LIST, PostfixValueKind::INTEGER));
+    }
+
+    #[test]
+    fn test_check_leaves_paths_to_run_time() {
+        // only one of the branches is mistyped
+        assert_eq!(well_typed("(postfix 1 (3 exec) (4) sel exec)", &PostfixConfig::core()), Ok(()));
+        // a mistyped body may be caught by its handler
+        let exception = PostfixConfig::core().with_extension(PostfixExtension::EXCEPTION);
+        assert_eq!(well_typed("(postfix 0 (3 exec) (pop 5) try)", &exception), Ok(()));
+        // the value copied from an unknown depth is never a sequence
+        assert_eq!(well_typed("(postfix 2 (1) 2 nget nget exec)", &PostfixConfig::core()), Ok(()));
+    }
+
+    test_case_with_config! [ PostfixConfig::core().with_type_check();
+        ( typed_program_runs, "(postfix 1 1 nget 0 lt (neg) () sel exec)", vec![-3], Ok(3) ),
+        ( typed_program_mistyped, "(postfix 0 3 exec)", vec![], Err(MistypedArgument { function: EXEC, location: vec![1], position: Some(Position { line: 1, column: 14 }), expected: PostfixValueKind::SEQUENCE, actual: PostfixValueKind::INTEGER }) ),
+    ];
+}
+
+#[cfg(test)]
+mod energy_suite {
+    use super::*;
+    use super::super::{
+        config::PostfixExtension,
+        energy::step_bound,
+        enums::PostfixProgram,
+        io::StandardIo,
+        programs::execute_program_counting_steps,
+        reports::analysis_report,
+    };
+
+    fn compile(code: &str, config: &PostfixConfig) -> PostfixProgram {
+        parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), config).unwrap()
+    }
+
+    // the bound holds for each run, and is reached when `exact`
+    fn assert_bounded(code: &str, arguments: Vec<i32>, exact: bool) {
+        let program = compile(code, &PostfixConfig::core());
+        let bound = step_bound(&program).unwrap();
+        let (_, steps) = execute_program_counting_steps(&program, &arguments, &PostfixConfig::core(), &mut StandardIo);
+        match exact {
+            true => assert_eq!(steps, bound, "{}", code),
+            false => assert!(steps <= bound, "{} took {} steps, bounded by {}", code, steps, bound),
+        }
+    }
+
+    #[test]
+    fn test_bound_reached_without_branches() {
+        assert_bounded("(postfix 2 add)", vec![3, 4], true);
+        assert_bounded("(postfix 1 4 add 5 mul 6 sub 7 div)", vec![3], true);
+        assert_bounded("(postfix 2 (mul sub) (1 nget mul) 4 nget swap exec swap exec)", vec![-10, 2], true);
+        assert_bounded("(postfix 1 ((3 nget swap exec) (2 mul swap exec) swap) (5 sub) swap exec exec)", vec![2], true);
+    }
+
+    #[test]
+    fn test_bound_holds_across_runs() {
+        assert_bounded("(postfix 1 1 nget 0 lt (0 swap sub) () sel exec)", vec![-7], false);
+        assert_bounded("(postfix 1 1 nget 0 lt (0 swap sub) () sel exec)", vec![6], false);
+        assert_bounded("(postfix 4 lt (add) (mul) sel exec)", vec![3, 4, 5, 6], false);
+        assert_bounded("(postfix 2 (1 nget) (0) sel exec)", vec![0, 3], false);
+        assert_bounded("(postfix 2 and not)", vec![6, 0], false);
+        assert_bounded("(postfix 1 abs square)", vec![-7], false);
+        assert_bounded("(postfix 2 min 3 max)", vec![1, 8], false);
+    }
+
+    #[test]
+    fn test_bound_holds_for_failing_runs() {
+        assert_bounded("(postfix 0 3 (2 mul) gt)", vec![], false);
+        assert_bounded("(postfix 0 3 exec)", vec![], false);
+        assert_bounded("(postfix 1 4 mul add)", vec![3], false);
+        assert_bounded("(postfix 2 4 sub div)", vec![4, 5], false);
+        assert_bounded("(postfix 2 add)", vec![3], false);
+    }
+
+    #[test]
+    fn test_unbounded_extensions() {
+        let loops = PostfixConfig::core().with_extension(PostfixExtension::LOOP);
+        assert_eq!(step_bound(&compile("(postfix 1 (1 nget mul) 3 repeat)", &loops)), None);
+        let memory = PostfixConfig::core().with_extension(PostfixExtension::MEMORY);
+        assert_eq!(step_bound(&compile("(postfix 0 ((1 get exec)) 1 put 1 get exec)", &memory)), None);
+        let exception = PostfixConfig::core().with_extension(PostfixExtension::EXCEPTION);
+        assert_eq!(step_bound(&compile("(postfix 0 (1 throw) (pop 2) try)", &exception)), None);
+        assert_eq!(step_bound(&compile("(postfix 0 (1 throw) exec)", &exception)), Some(4));
+    }
+
+    #[test]
+    fn test_analysis_report() {
+        let config = PostfixConfig::core();
+        assert_eq!(analysis_report(&compile("(postfix 2 add)", &config), &config), "parameters: 2\nstack effect: 2 -> 1\nstack check: ok\ntypes: [INTEGER]\nsteps: at most 1\n");
+        assert_eq!(
+            analysis_report(&compile("(postfix 1 (add) (mul) sel exec)", &config), &config),
+            "parameters: 1\nstack effect: 3 -> 1\nstack check: StackUnderflow { function: ARITHMETIC(ADD), expected_number_of_arguments: 2, available: 0 }\ntypes: unknown\nsteps: at most 6\n",
+        );
+    }
+}
+
+#[cfg(test)]
+mod optimizer_suite {
+    use super::*;
+    use super::super::{
+        config::{
+            PostfixExtension,
+            PostfixOverflow,
+            PostfixTyping,
+        },
+        enums::{
+            PostfixArithmetic::*,
+            PostfixCommand::*,
+            PostfixFunction::*,
+            PostfixCommand,
+            PostfixProgram,
+        },
+        io::StandardIo,
+        optimizer::{
+            PostfixOptimizer,
+            PostfixRewrite,
+        },
+        programs::execute_program_counting_steps,
+    };
+
+    fn compile(code: &str, config: &PostfixConfig) -> PostfixProgram {
+        parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), config).unwrap()
+    }
+
+    fn optimized(optimizer: PostfixOptimizer, code: &str) -> Vec<PostfixCommand> {
+        let config = PostfixConfig::core();
+        optimizer.optimize(&compile(code, &config), &config).commands
+    }
+
+    // each rewrite alone and all of them together give the same outcome, in no more steps
+    fn assert_preserved(code: &str, arguments: Vec<i32>, config: &PostfixConfig) {
+        let program = compile(code, config);
+        let (expected, steps) = execute_program_counting_steps(&program, &arguments, config, &mut StandardIo);
+        PostfixRewrite::ALL.iter().map(|rewrite| PostfixOptimizer::none().with(*rewrite)).chain([PostfixOptimizer::all()]).for_each(|optimizer| {
+            let (actual, optimized_steps) = execute_program_counting_steps(&optimizer.optimize(&program, config), &arguments, config, &mut StandardIo);
+            assert_eq!(actual, expected, "{}", code);
+            assert!(optimized_steps <= steps, "{}", code);
+        });
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::FOLDCONSTANTS), "(postfix 0 3 4 add 5 mul)"), vec![INTEGER(35)]);
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::FOLDCONSTANTS), "(postfix 0 (1) 2 swap)"), vec![INTEGER(2), EXECUTE(vec![INTEGER(1)])]);
+        // a failing computation is left for the run time
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::FOLDCONSTANTS), "(postfix 0 1 0 div)"), vec![INTEGER(1), INTEGER(0), SPECIAL(ARITHMETIC(DIV))]);
+        assert_eq!(optimized(PostfixOptimizer::all().without(PostfixRewrite::FOLDCONSTANTS), "(postfix 0 3 4 add)"), vec![INTEGER(3), INTEGER(4), SPECIAL(ARITHMETIC(ADD))]);
+    }
+
+    #[test]
+    fn test_select_known() {
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::SELECTKNOWN), "(postfix 0 0 (add) (sub) sel)"), vec![EXECUTE(vec![SPECIAL(ARITHMETIC(SUB))])]);
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::SELECTKNOWN), "(postfix 1 1 nget (add) (sub) sel)").len(), 5);
+        let strict = PostfixConfig::core().with_typing(PostfixTyping::STRICT);
+        assert_eq!(PostfixOptimizer::all().optimize(&compile("(postfix 0 1 2 3 sel)", &strict), &strict).commands.len(), 4);
+    }
+
+    #[test]
+    fn test_inline_exec() {
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::INLINEEXEC), "(postfix 1 (2 mul) exec)"), vec![INTEGER(2), SPECIAL(ARITHMETIC(MUL))]);
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::INLINEEXEC), "(postfix 0 ((() exec) exec))"), vec![EXECUTE(vec![])]);
+    }
+
+    #[test]
+    fn test_drop_pushes() {
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::DROPPUSHES), "(postfix 1 7 pop (add) pop)"), vec![]);
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::DROPPUSHES), "(postfix 2 add pop)"), vec![SPECIAL(ARITHMETIC(ADD)), SPECIAL(POP)]);
+    }
+
+    #[test]
+    fn test_drop_copies() {
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::DROPCOPIES), "(postfix 2 2 nget pop)"), vec![]);
+        // the copy may fail, with no argument there or with a sequence there
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::DROPCOPIES), "(postfix 2 3 nget pop)").len(), 3);
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::DROPCOPIES), "(postfix 0 (1) 1 nget pop)").len(), 4);
+    }
+
+    #[test]
+    fn test_cancel_swaps() {
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::CANCELSWAPS), "(postfix 2 swap swap)"), vec![]);
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::CANCELSWAPS), "(postfix 1 swap swap)"), vec![SPECIAL(SWAP), SPECIAL(SWAP)]);
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::CANCELSWAPS), "(postfix 0 (swap swap))"), vec![EXECUTE(vec![SPECIAL(SWAP), SPECIAL(SWAP)])]);
+    }
+
+    #[test]
+    fn test_copy_constants() {
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::COPYCONSTANTS), "(postfix 1 3 2 nget 2 nget)"), vec![INTEGER(3), INTEGER(2), SPECIAL(NGET), INTEGER(3)]);
+        assert_eq!(optimized(PostfixOptimizer::none().with(PostfixRewrite::COPYCONSTANTS), "(postfix 1 3 1 nget 2 nget)"), vec![INTEGER(3), INTEGER(3), INTEGER(3)]);
+        assert_eq!(optimized(PostfixOptimizer::all(), "(postfix 0 3 1 nget mul)"), vec![INTEGER(9)]);
+    }
+
+    #[test]
+    fn test_rewrites_carry_on() {
+        assert_eq!(optimized(PostfixOptimizer::all(), "(postfix 1 3 4 add 5 mul swap swap 1 nget pop (2 mul) exec 1 (add) (sub) sel exec)"), vec![INTEGER(70), SPECIAL(ARITHMETIC(ADD))]);
+        assert_eq!(optimized(PostfixOptimizer::all(), "(postfix 0 1 2 3 sel)"), vec![INTEGER(2)]);
+    }
+
+    #[test]
+    fn test_differential_core() {
+        let core = PostfixConfig::core();
+        assert_preserved("(postfix 1 4 add 5 mul 6 sub 7 div)", vec![3], &core);
+        assert_preserved("(postfix 0 3 4 add 5 mul 0 div)", vec![], &core);
+        assert_preserved("(postfix 2 swap swap sub)", vec![3, 4], &core);
+        assert_preserved("(postfix 1 swap swap)", vec![3], &core);
+        assert_preserved("(postfix 0 (1) 1 nget pop)", vec![], &core);
+        assert_preserved("(postfix 1 2 nget pop)", vec![3], &core);
+        assert_preserved("(postfix 0 (2 mul) 3 4 sel)", vec![], &core);
+        assert_preserved("(postfix 0 3 exec)", vec![], &core);
+        assert_preserved("(postfix 1 (2 (3) mul) exec)", vec![3], &core);
+        assert_preserved("(postfix 1 ((3 nget swap exec) (2 mul swap exec) swap) (5 sub) swap exec exec)", vec![2], &core);
+        assert_preserved("(postfix 2 (mul sub) (1 nget mul) 4 nget swap exec swap exec)", vec![-10, 2], &core);
+        assert_preserved("(postfix 1 1 nget 0 lt (0 swap sub) () sel exec)", vec![-7], &core);
+        assert_preserved("(postfix 2 and not 1 0 lt add)", vec![6, 0], &core);
+        assert_preserved("(postfix 0 2147483647 1 add)", vec![], &core);
+        assert_preserved("(postfix 0 7 pop pop)", vec![], &core);
+        assert_preserved("(postfix 0 1 0 (1 nget) (2 mul) sel exec)", vec![], &core);
+    }
+
+    #[test]
+    fn test_differential_dialects() {
+        let wrapping = PostfixConfig::core().with_extension(PostfixExtension::MATH).with_overflow(PostfixOverflow::WRAPPING);
+        assert_preserved("(postfix 0 2147483647 1 add 2 pow)", vec![], &wrapping);
+        let strict = PostfixConfig::core().with_typing(PostfixTyping::STRICT);
+        assert_preserved("(postfix 0 1 2 3 sel)", vec![], &strict);
+        assert_preserved("(postfix 1 0 lt 2 3 sel)", vec![-1], &strict);
+        let rational = PostfixConfig::core().with_extension(PostfixExtension::RATIONAL);
+        assert_preserved("(postfix 0 1 3 div 3 mul)", vec![], &rational);
+        let strings = PostfixConfig::core().with_extension(PostfixExtension::STRING);
+        assert_preserved(r#"(postfix 0 "12" int "3" concat)"#, vec![], &strings);
+        assert_preserved(r#"(postfix 0 "1" "2" concat int len)"#, vec![], &strings);
+        let lists = PostfixConfig::core().with_extension(PostfixExtension::LIST);
+        assert_preserved("(postfix 0 [1 2] 3 push 3 at)", vec![], &lists);
+        assert_preserved("(postfix 0 [1 2] 4 at)", vec![], &lists);
+        let exception = PostfixConfig::core().with_extension(PostfixExtension::EXCEPTION);
+        assert_preserved("(postfix 0 (1 0 div) (pop 5 swap swap) try)", vec![], &exception);
+    }
+}
+
+#[cfg(test)]
+mod specializer_suite {
+    use super::*;
+    use super::PostfixError::*;
+    use super::super::{
+        enums::{
+            PostfixArithmetic::*,
+            PostfixCommand::*,
+            PostfixFunction::*,
+            PostfixProgram,
+        },
+        io::StandardIo,
+        programs::execute_program_counting_steps,
+        specializer::specialize,
+    };
+
+    fn compile(code: &str) -> PostfixProgram {
+        parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), &PostfixConfig::core()).unwrap()
+    }
+
+    // the residual run on the unknown arguments gives what the program gives on all of them
+    fn assert_specialized(code: &str, known: &[bool], arguments: Vec<i32>) -> PostfixProgram {
+        let config = PostfixConfig::core();
+        let program = compile(code);
+        let given: Vec<Option<i32>> = arguments.iter().zip(known).map(|(argument, known)| known.then_some(*argument)).collect();
+        let residual = specialize(&program, &given, &config).unwrap();
+        let remaining: Vec<i32> = arguments.iter().zip(known).filter(|(_, known)| !**known).map(|(argument, _)| *argument).collect();
+        assert_eq!(residual.paramsize as usize, remaining.len());
+        let (expected, _) = execute_program_counting_steps(&program, &arguments, &config, &mut StandardIo);
+        let (actual, _) = execute_program_counting_steps(&residual, &remaining, &config, &mut StandardIo);
+        assert_eq!(actual, expected, "{} {:?}", code, given);
+        residual
+    }
+
+    #[test]
+    fn test_specialize_resolves_sel() {
+        let residual = assert_specialized("(postfix 2 1 nget 0 lt (neg) () sel exec add)", &[true, false], vec![-3, 4]);
+        assert_eq!(residual.commands, vec![INTEGER(3), SPECIAL(ARITHMETIC(ADD))]);
+        let residual = assert_specialized("(postfix 2 1 nget 0 lt (neg) () sel exec add)", &[true, false], vec![6, 4]);
+        assert_eq!(residual.commands, vec![INTEGER(6), SPECIAL(ARITHMETIC(ADD))]);
+    }
+
+    #[test]
+    fn test_specialize_inlines_exec() {
+        let residual = assert_specialized("(postfix 2 (mul sub) (1 nget mul) 4 nget swap exec swap exec)", &[true, true], vec![-10, 2]);
+        assert_eq!(residual.commands, vec![INTEGER(42)]);
+        assert_specialized("(postfix 2 (mul sub) (1 nget mul) 4 nget swap exec swap exec)", &[false, true], vec![-10, 2]);
+        assert_specialized("(postfix 2 (mul sub) (1 nget mul) 4 nget swap exec swap exec)", &[true, false], vec![-10, 2]);
+    }
+
+    #[test]
+    fn test_specialize_any_subset() {
+        let code = "(postfix (a b c x) a x x mul mul b x mul add c add)";
+        [[false, false, false, false], [true, true, true, false], [false, false, false, true], [true, false, true, false], [false, true, false, true], [true, true, true, true]].iter().for_each(|known| {
+            assert_specialized(code, known, vec![3, 4, 5, 2]);
+        });
+        assert_eq!(assert_specialized(code, &[true, true, true, true], vec![3, 4, 5, 2]).commands.last(), Some(&INTEGER(25)));
+        // copied in where it is read, below two unknown arguments
+        assert_eq!(assert_specialized("(postfix 3 3 nget mul add)", &[false, false, true], vec![1, 5, 6]).commands, vec![INTEGER(6), SPECIAL(ARITHMETIC(MUL)), SPECIAL(ARITHMETIC(ADD))]);
+    }
+
+    #[test]
+    fn test_specialize_keeps_errors() {
+        assert_specialized("(postfix 2 div)", &[true, false], vec![0, 4]);
+        assert_specialized("(postfix 2 swap exec)", &[false, true], vec![1, 4]);
+        assert_specialized("(postfix 1 2 nget)", &[true], vec![1]);
+        // a known argument under an unknown one is no longer there to be found past the arguments
+        assert_specialized("(postfix 2 pop pop pop 7)", &[false, true], vec![1, 5]);
+        assert_specialized("(postfix 3 pop pop pop pop 7)", &[false, true, true], vec![1, 5, 6]);
+    }
+
+    #[test]
+    fn test_specialize_rejects() {
+        let config = PostfixConfig::core();
+        assert_eq!(specialize(&compile("(postfix 2 add)"), &[Some(1)], &config).map(|_| ()), Err(WrongNumberOfKnownArguments { expected: 2, actual: 1 }));
+        assert_eq!(specialize(&compile("(postfix 1+ add)"), &[Some(1)], &config).map(|_| ()), Err(UnsupportedSpecialization));
+        // a known argument below two unknown ones must be read by nget
+        assert_eq!(specialize(&compile("(postfix 3 add add)"), &[None, None, Some(1)], &config).map(|_| ()), Err(UnsupportedSpecialization));
+    }
+}
+
+#[cfg(test)]
+mod symbolic_suite {
+    use super::*;
+    use super::PostfixError::*;
+    use super::super::{
+        config::PostfixExtension,
+        enums::{
+            PostfixArithmetic,
+            PostfixCommand::*,
+            PostfixFunction::*,
+            PostfixProgram,
+        },
+        io::StandardIo,
+        programs::execute_program_counting_steps,
+        symbolic::{
+            argument_vectors,
+            execute_symbolically,
+            PostfixPath,
+        },
+    };
+
+    fn compile(code: &str) -> PostfixProgram {
+        parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), &PostfixConfig::core()).unwrap()
+    }
+
+    // every path agrees with the interpreter on the arguments it is taken for, and exactly one path is
+    fn assert_paths(code: &str) -> Vec<PostfixPath> {
+        let config = PostfixConfig::core();
+        let program = compile(code);
+        let paths = execute_symbolically(&program).unwrap();
+        argument_vectors(program.paramsize as usize, &[0, 1, -1, 2, -2, 7, i32::MAX, i32::MIN]).for_each(|arguments| {
+            let taken: Vec<&PostfixPath> = paths.iter().filter(|path| path.holds(&arguments)).collect();
+            assert_eq!(taken.len(), 1, "{} {:?}", code, arguments);
+            let expected = match &taken[0].outcome {
+                Ok(expression) => expression.evaluate(&arguments),
+                Err(error) => Err(error.clone()),
+            };
+            let (actual, _) = execute_program_counting_steps(&program, &arguments, &config, &mut StandardIo);
+            assert_eq!(actual, expected, "{} {:?}", code, arguments);
+        });
+        paths
+    }
+
+    #[test]
+    fn test_symbolic_piecewise() {
+        let paths = assert_paths("(postfix 2 (1 nget) (0) sel exec)");
+        let shown: Vec<String> = paths.iter().map(|path| path.to_string()).collect();
+        assert_eq!(shown, vec!["if arg1 != 0: arg2", "if arg1 = 0: 0"]);
+        let paths = assert_paths("(postfix 2 abs 1 nget mul add)");
+        assert_eq!(paths.len(), 7);
+    }
+
+    #[test]
+    fn test_symbolic_straight_line() {
+        let paths = assert_paths("(postfix (a b c x) a x x mul mul b x mul add c add)");
+        assert_eq!(paths.len(), 6);
+        assert!(paths.last().unwrap().to_string().ends_with("fits: (((arg1 * (arg4 * arg4)) + (arg2 * arg4)) + arg3)"));
+    }
+
+    #[test]
+    fn test_symbolic_divide_by_zero() {
+        let paths = assert_paths("(postfix 2 div)");
+        let failing: Vec<&PostfixPath> = paths.iter().filter(|path| path.outcome == Err(DivideByZero)).collect();
+        assert_eq!(failing.len(), 1);
+        assert_eq!(failing[0].witness(2, &[1, 0, -1]), Some(vec![0, 1]));
+        let paths = assert_paths("(postfix 2 1 nget 0 lt (neg) () sel exec div)");
+        let failing: Vec<&PostfixPath> = paths.iter().filter(|path| path.outcome == Err(DivideByZero)).collect();
+        assert_eq!(failing.len(), 1);
+        assert_eq!(failing[0].witness(2, &[1, 0, -1]), Some(vec![0, 1]));
+        assert_eq!(paths.len(), 4);
+    }
+
+    #[test]
+    fn test_argument_vectors() {
+        assert_eq!(argument_vectors(2, &[0, 1]).collect::<Vec<_>>(), vec![vec![0, 0], vec![1, 0], vec![0, 1], vec![1, 1]]);
+        assert_eq!(argument_vectors(0, &[0, 1]).collect::<Vec<_>>(), vec![Vec::<i32>::new()]);
+        assert_eq!(argument_vectors(1, &[]).count(), 0);
+        // far more vectors than could be counted are made one at a time
+        assert_eq!(argument_vectors(64, &[0, 1, 2]).nth(2), Some([vec![2], vec![0; 63]].concat()));
+    }
+
+    #[test]
+    fn test_symbolic_overflow() {
+        let paths = assert_paths("(postfix 2 div)");
+        let overflowing: Vec<&PostfixPath> = paths.iter().filter(|path| matches!(path.outcome, Err(ArithmeticOverflow { .. }))).collect();
+        assert_eq!(overflowing.len(), 1);
+        assert_eq!(overflowing[0].witness(2, &[1, -1, i32::MIN]), Some(vec![-1, i32::MIN]));
+        assert_eq!(assert_paths("(postfix 1 2 mul)").len(), 2);
+        // a result that always fits takes no decision
+        assert_eq!(assert_paths("(postfix 1 0 add)").iter().map(|path| path.to_string()).collect::<Vec<_>>(), vec!["always: (arg1 + 0)"]);
+        assert_eq!(assert_paths("(postfix 1 0 lt 2 3 sel 7 mul)").len(), 2);
+    }
+
+    #[test]
+    fn test_symbolic_final_states() {
+        let paths = assert_paths("(postfix 1 (1) (2) sel)");
+        assert_eq!(paths.len(), 2);
+        assert!(paths.iter().all(|path| matches!(path.outcome, Err(NonNumeralFinalState { .. }))));
+        assert_paths("(postfix 1 pop)");
+        assert_paths("(postfix 1 pop pop)");
+    }
+
+    #[test]
+    fn test_symbolic_unsupported() {
+        assert_eq!(execute_symbolically(&compile("(postfix 1 1 nget nget)")), Err(UnsupportedSymbolicCommand(SPECIAL(NGET))));
+        assert_eq!(execute_symbolically(&compile("(postfix 1+ pop)")), Err(UnsupportedSymbolicProgram));
+        // functions beyond the core are turned down before any path is taken, even where never run
+        let math = PostfixConfig::core().with_extension(PostfixExtension::MATH);
+        let extended = |code: &str| parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), &math).unwrap();
+        assert_eq!(execute_symbolically(&extended("(postfix 2 pow)")), Err(UnsupportedSymbolicCommand(SPECIAL(ARITHMETIC(PostfixArithmetic::POW)))));
+        assert_eq!(execute_symbolically(&extended("(postfix 1 (1 shl) pop)")), Err(UnsupportedSymbolicCommand(SPECIAL(ARITHMETIC(PostfixArithmetic::SHL)))));
+    }
+
+    #[test]
+    fn test_symbolic_missing_arguments() {
+        let paths = execute_symbolically(&compile("(postfix 2 div)")).unwrap();
+        assert!(paths.iter().all(|path| !path.holds(&[1])));
+        // only dividing by zero is decided by the divisor alone
+        assert_eq!(paths.iter().filter_map(|path| path.witness(1, &[1, 0, -1])).collect::<Vec<_>>(), vec![vec![0]]);
+        // a path deciding nothing still reads the arguments of its result
+        let paths = execute_symbolically(&compile("(postfix 1 0 add)")).unwrap();
+        assert!(!paths[0].holds(&[]));
+        assert!(paths[0].holds(&[5]));
+        assert_eq!(paths[0].outcome.as_ref().unwrap().evaluate(&[]), Err(WrongNumberOfArguments { expected: 1, actual: vec![] }));
+    }
+}
+
+#[cfg(test)]
+mod equivalence_suite {
+    use super::*;
+    use super::PostfixError::*;
+    use super::super::{
+        enums::PostfixProgram,
+        equivalence::{
+            check_equivalence,
+            EquivalenceBounds,
+            PostfixEquivalence::{self, *},
+        },
+    };
+
+    fn compile(code: &str) -> PostfixProgram {
+        parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), &PostfixConfig::core()).unwrap()
+    }
+
+    fn equivalence(left: &str, right: &str, bounds: &EquivalenceBounds) -> Result<PostfixEquivalence, PostfixError> {
+        check_equivalence(&compile(left), &compile(right), bounds, &PostfixConfig::core())
+    }
+
+    #[test]
+    fn test_equivalent_exhaustive() {
+        assert_eq!(equivalence("(postfix 1 2 mul)", "(postfix 1 1 nget add)", &EquivalenceBounds::default()), Ok(EQUIVALENT { tested: 9, exhaustive: true }));
+        assert_eq!(equivalence("(postfix 2 swap swap sub)", "(postfix 2 sub)", &EquivalenceBounds::new(-3, 3)), Ok(EQUIVALENT { tested: 49, exhaustive: true }));
+    }
+
+    #[test]
+    fn test_minimal_counterexample() {
+        assert_eq!(equivalence("(postfix 1 2 mul)", "(postfix 1 3 mul)", &EquivalenceBounds::default()),
+            Ok(COUNTEREXAMPLE { arguments: vec![-1], left: Ok(-2), right: Ok(-3) }));
+        assert_eq!(equivalence("(postfix 2 sub)", "(postfix 2 swap sub)", &EquivalenceBounds::default()),
+            Ok(COUNTEREXAMPLE { arguments: vec![-1, 0], left: Ok(1), right: Ok(-1) }));
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        // the failing operand differs, the kind of error does not
+        assert_eq!(equivalence("(postfix 1 () add)", "(postfix 1 () swap add)", &EquivalenceBounds::default()), Ok(EQUIVALENT { tested: 9, exhaustive: true }));
+        assert_eq!(equivalence("(postfix 1 0 div)", "(postfix 1 0 div pop 1)", &EquivalenceBounds::default()), Ok(EQUIVALENT { tested: 9, exhaustive: true }));
+        assert_eq!(equivalence("(postfix 1 1 swap div)", "(postfix 1 1 nget 0 eq (0) (1 swap div) sel exec)", &EquivalenceBounds::default()),
+            Ok(COUNTEREXAMPLE { arguments: vec![0], left: Err(DivideByZero), right: Ok(0) }));
+    }
+
+    #[test]
+    fn test_sampled() {
+        let bounds = EquivalenceBounds::new(-1000, 1000).with_exhaustive_limit(100).with_samples(50);
+        assert_eq!(equivalence("(postfix 2 add)", "(postfix 2 swap add)", &bounds), Ok(EQUIVALENT { tested: 50, exhaustive: false }));
+        // the counterexample drawn is moved towards zero
+        assert_eq!(equivalence("(postfix 2 1 nget 500 gt (1) (0) sel exec)", "(postfix 2 0)", &bounds.with_seed(7)),
+            Ok(COUNTEREXAMPLE { arguments: vec![501, 0], left: Ok(1), right: Ok(0) }));
+        // the whole range of integers is only ever sampled from
+        let whole = EquivalenceBounds::new(i32::MIN, i32::MAX).with_samples(50);
+        assert_eq!(equivalence("(postfix 1 0 add)", "(postfix 1)", &whole), Ok(EQUIVALENT { tested: 50, exhaustive: false }));
+    }
+
+    #[test]
+    fn test_mismatched_parameters() {
+        assert_eq!(equivalence("(postfix 1 2 mul)", "(postfix 2 add)", &EquivalenceBounds::default()), Err(MismatchedParameters { left: 1, right: 2 }));
+    }
+}
+
+#[cfg(test)]
+mod lints_suite {
+    use super::*;
+    use super::super::{
+        lexer::Position,
+        lints::{
+            lint_code,
+            PostfixLintKind::{self, *},
+            PostfixSeverity,
+        },
+    };
+
+    fn kinds(code: &str) -> Vec<(PostfixLintKind, Vec<usize>)> {
+        lint_code(code, &PostfixConfig::core()).unwrap().into_iter().map(|lint| (lint.kind, lint.location)).collect()
+    }
+
+    #[test]
+    fn test_lint_clean() {
+        assert_eq!(kinds("(postfix 2 1 nget 0 lt (neg) () sel exec add)"), vec![]);
+        assert_eq!(kinds("(postfix (a b) a b max)"), vec![]);
+        assert_eq!(kinds("(postfix 1 (2 mul) exec)"), vec![]);
+    }
+
+    #[test]
+    fn test_lint_constant_condition() {
+        assert_eq!(kinds("(postfix 1 0 (2) (3) sel exec add)"), vec![(UNUSEDSEQUENCE, vec![1]), (CONSTANTCONDITION, vec![3])]);
+        assert_eq!(kinds("(postfix 0 1 2 3 sel)"), vec![(CONSTANTCONDITION, vec![3])]);
+    }
+
+    #[test]
+    fn test_lint_unused_sequence() {
+        assert_eq!(kinds("(postfix 1 (1 add) pop)"), vec![(UNUSEDSEQUENCE, vec![0])]);
+        assert_eq!(kinds("(postfix 1 (1 add) swap)"), vec![(UNUSEDSEQUENCE, vec![0])]);
+        // whichever is selected, the result is dropped
+        assert_eq!(kinds("(postfix 1 (1) (2) sel pop 3)"), vec![(UNUSEDSEQUENCE, vec![0]), (UNUSEDSEQUENCE, vec![1])]);
+    }
+
+    #[test]
+    fn test_lint_no_op_pairs() {
+        assert_eq!(kinds("(postfix 2 swap swap sub)"), vec![(NOOPPAIR, vec![0])]);
+        assert_eq!(kinds("(postfix 1 (1 nget pop 0 add) exec)"), vec![(NOOPPAIR, vec![0, 0]), (NOOPPAIR, vec![0, 3])]);
+        assert_eq!(kinds("(postfix 1 7 pop)"), vec![(NOOPPAIR, vec![0])]);
+    }
+
+    #[test]
+    fn test_lint_unused_parameter() {
+        assert_eq!(kinds("(postfix 2 pop)"), vec![(UNUSEDPARAMETER, vec![])]);
+        assert_eq!(kinds("(postfix (a b) a)"), vec![(UNUSEDPARAMETER, vec![])]);
+        assert_eq!(lint_code("(postfix (a b c) a c add)", &PostfixConfig::core()).unwrap()[0].message, "parameter 2 is never read");
+        // a parameter taken on one of the runs is read
+        assert_eq!(kinds("(postfix 3 (pop) (swap pop) sel exec)"), vec![]);
+    }
+
+    #[test]
+    fn test_lint_comparison_arithmetic() {
+        assert_eq!(kinds("(postfix 2 lt 1 add)"), vec![(COMPARISONARITHMETIC, vec![2])]);
+        assert_eq!(kinds("(postfix 2 1 nget 0 gt mul add)"), vec![(COMPARISONARITHMETIC, vec![4])]);
+        assert_eq!(kinds("(postfix 2 lt 1 0 sel)"), vec![]);
+    }
+
+    #[test]
+    fn test_lint_sequence_result() {
+        assert_eq!(kinds("(postfix 0 (1))"), vec![(SEQUENCERESULT, vec![0])]);
+        assert_eq!(kinds("(postfix 1 (1) (2) sel)"), vec![(SEQUENCERESULT, vec![0])]);
+        assert_eq!(kinds("(postfix 1 (1) 2 sel)"), vec![]);
+        let lints = lint_code("(postfix 0 (1))", &PostfixConfig::core()).unwrap();
+        assert_eq!(lints[0].kind.severity(), PostfixSeverity::ERROR);
+        assert_eq!(lints[0].to_string(), "error[L006] sequence-result at 1:12: the program always ends with a sequence on top");
+    }
+
+    #[test]
+    fn test_lint_suppressed() {
+        // a comment before `(postfix` speaks for the whole program
+        assert_eq!(kinds("; allow(L003, L004)\n(postfix 2 pop swap swap)"), vec![]);
+        assert_eq!(kinds("; allow(nothing)\n(postfix 2 pop swap swap)"), vec![(UNUSEDPARAMETER, vec![]), (NOOPPAIR, vec![1])]);
+        // a comment after the program speaks for nothing
+        assert_eq!(kinds("(postfix 2 pop swap swap) ; allow(no-op-pair)"), vec![(UNUSEDPARAMETER, vec![]), (NOOPPAIR, vec![1])]);
+        // `allow(` is found anywhere in the comment
+        assert_eq!(kinds("; the pair is kept on purpose, allow(no-op-pair)\n(postfix 2 pop swap swap)"), vec![(UNUSEDPARAMETER, vec![])]);
+    }
+
+    #[test]
+    fn test_lint_suppressed_per_line_and_form() {
+        // a comment speaks for the next line only
+        assert_eq!(kinds("(postfix 1 ; allow(no-op-pair)\n swap swap\n 7 pop)"), vec![(NOOPPAIR, vec![2])]);
+        // or for the whole form the next line opens, over all its lines
+        assert_eq!(kinds("(postfix 1\n ; allow(no-op-pair)\n (swap swap\n 7 pop)\n exec 7 pop)"), vec![(NOOPPAIR, vec![2])]);
+        // a lint of the whole program is only allowed for the whole program
+        assert_eq!(kinds("(postfix 2 ; allow(unused-parameter)\n pop)"), vec![(UNUSEDPARAMETER, vec![])]);
+    }
+
+    #[test]
+    fn test_lint_positions() {
+        let lints = lint_code("(postfix 0\n  7 pop\n  (1))", &PostfixConfig::core()).unwrap();
+        assert_eq!(lints.iter().map(|lint| lint.position).collect::<Vec<_>>(),
+            vec![Some(Position { line: 2, column: 3 }), Some(Position { line: 3, column: 3 })]);
+        assert_eq!(lints[1].to_string(), "error[L006] sequence-result at 3:3: the program always ends with a sequence on top");
+        assert_eq!(lint_code("(postfix 2 pop)", &PostfixConfig::core()).unwrap()[0].position, None);
+    }
+}
+
+#[cfg(test)]
+mod fixes_suite {
+    use super::*;
+    use super::super::{
+        config::PostfixOverflow,
+        enums::{
+            PostfixCommand::*,
+            PostfixFunction::*,
+            PostfixProgram,
+        },
+        equivalence::{
+            EquivalenceBounds,
+            PostfixEquivalence::*,
+        },
+        fixes::{
+            find_dead_code,
+            fix_dead_code,
+            PostfixDeadCode::{self, *},
+        },
+    };
+
+    fn compile(code: &str, config: &PostfixConfig) -> PostfixProgram {
+        parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), config).unwrap()
+    }
+
+    // the fixed program as written, which must behave as the original on every small argument vector
+    fn fixed(code: &str, config: &PostfixConfig) -> Option<(String, Vec<PostfixDeadCode>)> {
+        let program = compile(code, config);
+        fix_dead_code(&program, &EquivalenceBounds::new(-3, 3), config).unwrap().map(|fix| {
+            assert!(matches!(fix.verification, EQUIVALENT { exhaustive: true, .. }), "{} {:?}", code, fix.verification);
+            (fix.program.to_string(), fix.removed)
+        })
+    }
+
+    #[test]
+    fn test_fix_dead_commands() {
+        let config = PostfixConfig::core();
+        assert_eq!(fixed("(postfix 1 5 pop 2 mul)", &config), Some(("(postfix 1 2 mul)".to_string(), vec![COMMANDS { location: vec![0], commands: vec![INTEGER(5), SPECIAL(POP)] }])));
+        assert_eq!(fixed("(postfix 1 1 nget 0 lt pop 3 4 add pop)", &config).map(|(program, _)| program), Some("(postfix 1)".to_string()));
+        assert_eq!(fixed("(postfix 1 (3 pop 2 mul) exec)", &config), Some(("(postfix 1 (2 mul) exec)".to_string(), vec![COMMANDS { location: vec![0, 0], commands: vec![INTEGER(3), SPECIAL(POP)] }])));
+        assert_eq!(fixed("(postfix 1 (1 add) pop 1 nget 2 div pop)", &config).map(|(program, _)| program), Some("(postfix 1)".to_string()));
+    }
+
+    #[test]
+    fn test_fix_keeps_failing_commands() {
+        let config = PostfixConfig::core();
+        assert_eq!(fixed("(postfix 1 1 nget 0 div pop)", &config), None);
+        assert_eq!(fixed("(postfix 2 add)", &config), None);
+        // a sum may overflow, unless it wraps around
+        assert_eq!(fixed("(postfix 1 1 nget 1 nget add pop)", &config), None);
+        assert_eq!(fixed("(postfix 1 1 nget 1 nget add pop)", &config.clone().with_overflow(PostfixOverflow::WRAPPING)).map(|(program, _)| program), Some("(postfix 1)".to_string()));
+        // a sequence cannot be copied
+        assert_eq!(fixed("(postfix 0 (1) 1 nget pop pop 2)", &config), None);
+    }
+
+    #[test]
+    fn test_fix_unused_parameters() {
+        let config = PostfixConfig::core();
+        assert_eq!(fixed("(postfix 2 pop)", &config), Some(("(postfix 1)".to_string(), vec![PARAMETER(1)])));
+        assert_eq!(fixed("(postfix 3 1 nget 0 lt pop pop 1 nget mul)", &config).map(|(program, removed)| (program, removed.len())), Some(("(postfix 1 1 nget mul)".to_string(), 3)));
+        // indices reaching past the parameter are lowered
+        assert_eq!(fixed("(postfix 2 2 nget 3 nget add)", &config), Some(("(postfix 1 1 nget 2 nget add)".to_string(), vec![PARAMETER(1)])));
+        // a copy dropped leaves its parameter unused
+        assert_eq!(fixed("(postfix 2 2 nget pop)", &config).map(|(program, _)| program), Some("(postfix 1)".to_string()));
+    }
+
+    #[test]
+    fn test_fix_shows_compiled_program() {
+        // the prelude comes inlined in the fixed program
+        assert_eq!(fixed("(postfix 1 5 pop neg)", &PostfixConfig::core()).map(|(program, _)| program), Some("(postfix 1 0 swap sub)".to_string()));
+    }
+
+    #[test]
+    fn test_fix_keeps_used_parameters() {
+        let config = PostfixConfig::core();
+        assert_eq!(find_dead_code(&compile("(postfix 2 swap pop)", &config), &config), vec![]);
+        assert_eq!(find_dead_code(&compile("(postfix 2 (pop) (swap pop) sel exec)", &config), &config), vec![]);
+        assert_eq!(find_dead_code(&compile("(postfix 2+ pop pop)", &config), &config), vec![]);
+    }
+}
+
+#[cfg(test)]
+mod superoptimizer_suite {
+    use super::*;
+    use super::super::{
+        enums::PostfixProgram,
+        equivalence::{
+            check_equivalence,
+            EquivalenceBounds,
+            PostfixEquivalence,
+        },
+        superoptimizer::PostfixSuperoptimizer,
+    };
+
+    fn compile(code: &str) -> PostfixProgram {
+        parse_tokens_to_program(parse_code_to_tokens(code).unwrap(), &PostfixConfig::core()).unwrap()
+    }
+
+    // the shortest program found as written, checked once more against the original
+    fn superoptimized(code: &str, superoptimizer: &PostfixSuperoptimizer) -> Result<Option<String>, PostfixError> {
+        let config = PostfixConfig::core().without_trace();
+        let program = compile(code);
+        superoptimizer.superoptimize(&program, &config).map(|found| found.map(|found| {
+            assert!(matches!(check_equivalence(&program, &found, &EquivalenceBounds::new(-5, 5), &config), Ok(PostfixEquivalence::EQUIVALENT { .. })), "{} {}", code, found);
+            found.to_string()
+        }))
+    }
+
+    #[test]
+    fn test_superoptimize_shorter() {
+        let superoptimizer = PostfixSuperoptimizer::default();
+        assert_eq!(superoptimized("(postfix 1 1 nget 1 nget add 0 add)", &superoptimizer), Ok(Some("(postfix 1 2 mul)".to_string())));
+        assert_eq!(superoptimized("(postfix 2 swap swap sub)", &superoptimizer), Ok(Some("(postfix 2 sub)".to_string())));
+        assert_eq!(superoptimized("(postfix 2 1 nget 1 nget mul swap pop)", &superoptimizer), Ok(Some("(postfix 2 1 nget mul)".to_string())));
+        // the literals of the program are among the candidates
+        assert_eq!(superoptimized("(postfix 0 (3 4 add) exec)", &superoptimizer), Ok(Some("(postfix 0 3 4 add)".to_string())));
+    }
+
+    #[test]
+    fn test_superoptimize_errors() {
+        let superoptimizer = PostfixSuperoptimizer::default();
+        // the same kind of error is as good as the same result
+        assert_eq!(superoptimized("(postfix 1 1 nget 0 div add)", &superoptimizer), Ok(Some("(postfix 1 0 div)".to_string())));
+        assert_eq!(superoptimized("(postfix 1+ pop)", &superoptimizer), Err(PostfixError::UnsupportedSuperoptimization));
+        // too many parameters for the test vectors to cover
+        assert_eq!(superoptimized("(postfix 30 1 pop 1)", &superoptimizer), Err(PostfixError::UnsupportedSuperoptimization));
+        assert_eq!(superoptimized("(postfix 10 1 pop 1)", &superoptimizer), Err(PostfixError::UnsupportedSuperoptimization));
+        assert_eq!(superoptimized("(postfix 3 1 pop 1)", &superoptimizer.with_max_examples(100)), Err(PostfixError::UnsupportedSuperoptimization));
+        assert_eq!(superoptimized("(postfix 3 1 pop 1)", &PostfixSuperoptimizer::default()), Ok(Some("(postfix 3 1)".to_string())));
+    }
+
+    #[test]
+    fn test_superoptimize_bounded() {
+        let superoptimizer = PostfixSuperoptimizer::default().with_max_length(2);
+        assert_eq!(superoptimized("(postfix 1 abs)", &superoptimizer), Ok(None));
+        assert_eq!(superoptimized("(postfix 1 40 2 add add)", &superoptimizer), Ok(None));
+        assert_eq!(superoptimized("(postfix 1 40 2 add add)", &superoptimizer.with_literals(vec![42])), Ok(Some("(postfix 1 42 add)".to_string())));
+        // only programs shorter than the original are searched, a sequence counting one plus its body
+        let superoptimizer = PostfixSuperoptimizer::default();
+        assert_eq!(superoptimized("(postfix 2 add)", &superoptimizer), Ok(None));
+        assert_eq!(superoptimized("(postfix 0)", &superoptimizer), Ok(None));
+        assert_eq!(superoptimized("(postfix 2 1 nget mul)", &superoptimizer), Ok(None));
+        assert_eq!(superoptimized("(postfix 1 (1) exec add)", &superoptimizer), Ok(Some("(postfix 1 1 add)".to_string())));
+    }
+
+    #[test]
+    fn test_synthesize() {
+        let config = PostfixConfig::core();
+        let superoptimizer = PostfixSuperoptimizer::default();
+        let found = |paramsize, examples: &[(Vec<i32>, i32)]| superoptimizer.synthesize(paramsize, examples, &config).map(|program| program.to_string());
+        assert_eq!(found(2, &[(vec![3, 4], 7), (vec![1, -2], -1)]), Some("(postfix 2 add)".to_string()));
+        assert_eq!(found(1, &[(vec![2], 4), (vec![3], 9), (vec![-1], 1)]), Some("(postfix 1 1 nget mul)".to_string()));
+        assert_eq!(found(1, &[(vec![5], 1), (vec![-5], 0), (vec![0], 0)]), Some("(postfix 1 0 gt)".to_string()));
+    }
 }
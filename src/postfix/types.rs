@@ -0,0 +1,263 @@
+use super::{
+    config::{
+        PostfixConfig,
+        PostfixExtension,
+        PostfixTyping,
+    },
+    effects::{
+        sequence_effect,
+        StackEffect,
+    },
+    enums::{
+        PostfixArithmetic,
+        PostfixCommand,
+        PostfixFunction,
+        PostfixProgram,
+        PostfixValueKind,
+    },
+    errors::PostfixError,
+};
+
+
+// type of a stack slot as reported by the checker
+#[derive(Debug,Clone,PartialEq)]
+pub enum PostfixType {
+    INTEGER,
+    // an executable sequence, with its effect when it can be inferred
+    SEQUENCE(Option<StackEffect>),
+    UNKNOWN,
+}
+
+// what the checker knows of a slot: sequences keep their body and where it was written
+#[derive(Debug,Clone,PartialEq)]
+enum SlotType {
+    INTEGER(Option<i32>),
+    // a value that cannot be executed, of a kind left to the dialect
+    DATA,
+    SEQUENCE(Vec<usize>, Vec<PostfixCommand>),
+    // one of two sequences, as chosen by a sel
+    EITHER((Vec<usize>, Vec<PostfixCommand>), (Vec<usize>, Vec<PostfixCommand>)),
+    UNKNOWN,
+}
+
+impl SlotType {
+
+    // the type of a slot that holds one or the other
+    fn join(self, other: SlotType) -> SlotType {
+        use SlotType::*;
+        match (self, other) {
+            (left, right) if left == right => left,
+            (INTEGER(_), INTEGER(_)) => INTEGER(None),
+            (INTEGER(_) | DATA, INTEGER(_) | DATA) => DATA,
+            (SEQUENCE(left, left_commands), SEQUENCE(right, right_commands)) => EITHER((left, left_commands), (right, right_commands)),
+            (_, _) => UNKNOWN,
+        }
+    }
+
+    // the kind of the value when the checker is sure of it
+    fn kind(&self) -> Option<PostfixValueKind> {
+        use SlotType::*;
+        match self {
+            INTEGER(_) => Some(PostfixValueKind::INTEGER),
+            SEQUENCE(..) | EITHER(..) => Some(PostfixValueKind::SEQUENCE),
+            DATA | UNKNOWN => None,
+        }
+    }
+
+    fn public(&self) -> PostfixType {
+        use SlotType::*;
+        match self {
+            INTEGER(_) => PostfixType::INTEGER,
+            SEQUENCE(_, commands) => PostfixType::SEQUENCE(sequence_effect(commands)),
+            EITHER((_, left), (_, right)) => match (sequence_effect(left), sequence_effect(right)) {
+                (Some(left), Some(right)) if left == right => PostfixType::SEQUENCE(Some(left)),
+                (_, _) => PostfixType::SEQUENCE(None),
+            },
+            DATA | UNKNOWN => PostfixType::UNKNOWN,
+        }
+    }
+}
+
+// the slots of the stack from the bottom; when the stack is not closed, integer arguments lie below them
+#[derive(Debug,Clone,PartialEq)]
+struct TypeStack {
+    slots: Vec<SlotType>,
+    closed: bool,
+}
+
+impl TypeStack {
+
+    // the top `count` slots, deepest first, None when a closed stack holds fewer
+    fn pop(&mut self, count: usize) -> Option<Vec<SlotType>> {
+        match (self.slots.len() >= count, self.closed) {
+            (true, _) => Some(self.slots.split_off(self.slots.len() - count)),
+            (false, true) => None,
+            (false, false) => Some(std::iter::repeat_n(SlotType::INTEGER(None), count - self.slots.len()).chain(self.slots.drain(..)).collect()),
+        }
+    }
+
+    fn push(mut self, slot: SlotType) -> Self {
+        self.slots.push(slot);
+        self
+    }
+
+    // the stack after either of two paths, None when they disagree on its shape
+    fn join(self, other: TypeStack) -> Option<TypeStack> {
+        match self.slots.len() == other.slots.len() {
+            true => Some(TypeStack {
+                slots: self.slots.into_iter().zip(other.slots).map(|(left, right)| left.join(right)).collect(),
+                closed: self.closed,
+            }),
+            false => None,
+        }
+    }
+}
+
+// rejects a slot whose kind is known not to be accepted where `expected` is
+fn require(function: PostfixFunction, location: &[usize], expected: PostfixValueKind, slot: &SlotType, config: &PostfixConfig) -> Result<(), PostfixError> {
+    use PostfixValueKind::*;
+    let accepted = match (expected, slot.kind()) {
+        (_, None) => true,
+        (SEQUENCE, Some(actual)) => actual == SEQUENCE,
+        (INTEGER | RATIONAL, Some(actual)) => actual == INTEGER,
+        (BOOLEAN, Some(actual)) => actual == INTEGER && config.typing == PostfixTyping::LOOSE,
+        (STRING | LIST | FUNCTION, Some(_)) => false,
+    };
+    match (accepted, slot.kind()) {
+        (false, Some(actual)) => Err(PostfixError::MistypedArgument { function, location: location.to_owned(), position: None, expected, actual }),
+        (_, _) => Ok(()),
+    }
+}
+
+// integers give an integer back, unless the dialect makes it a boolean or a rational
+fn arithmetic_type(operator: PostfixArithmetic, pre: &SlotType, post: &SlotType, config: &PostfixConfig) -> SlotType {
+    use PostfixArithmetic::*;
+    match (operator, pre, post) {
+        (EQ | NE | GT | GE | LT | LE, _, _) if config.typing == PostfixTyping::STRICT => SlotType::DATA,
+        (DIV, _, _) if config.extensions.contains(&PostfixExtension::RATIONAL) => SlotType::DATA,
+        (_, SlotType::INTEGER(_), SlotType::INTEGER(_)) => SlotType::INTEGER(None),
+        (_, _, _) => SlotType::DATA,
+    }
+}
+
+// the stack after running `commands` written at `location`: Ok(None) once it depends on values only known
+// at run time, an error when every way of running them is mistyped
+fn analyze(commands: &[PostfixCommand], location: &[usize], stack: TypeStack, config: &PostfixConfig) -> Result<Option<TypeStack>, PostfixError> {
+    commands.iter().enumerate().try_fold(Some(stack), |stack, (index, command)| match stack {
+        Some(stack) => step(command, &[location, &[index]].concat(), stack, config),
+        None => Ok(None),
+    })
+}
+
+fn step(command: &PostfixCommand, location: &[usize], mut stack: TypeStack, config: &PostfixConfig) -> Result<Option<TypeStack>, PostfixError> {
+    use PostfixCommand::*;
+    use PostfixFunction::*;
+    use PostfixValueKind as Kind;
+    let function = match command {
+        EXECUTE(commands) => return Ok(Some(stack.push(SlotType::SEQUENCE(location.to_owned(), commands.to_owned())))),
+        INTEGER(value) => return Ok(Some(stack.push(SlotType::INTEGER(Some(*value))))),
+        RATIONAL(_) | BOOLEAN(_) | STRING(_) | LIST(_) => return Ok(Some(stack.push(SlotType::DATA))),
+        SPECIAL(function) => *function,
+    };
+    let arity = match (function.effect(), function) {
+        (Some((inputs, _)), _) => inputs,
+        (None, EXEC | THROW) => 1,
+        (None, REPEAT | ITERATION(_) | TRY | MAP) => 2,
+        (None, FOLD | FOLDING | MAPPING(_)) => 3,
+        // the arity of a native is only known to the host
+        (None, _) => return Ok(None),
+    };
+    // an underflow is left to the stack check
+    let slots = match stack.pop(arity) {
+        Some(slots) => slots,
+        None => return Ok(None),
+    };
+    let require = |expected, slot: &SlotType| require(function, location, expected, slot, config);
+    match (function, slots.as_slice()) {
+        (EXEC, [SlotType::SEQUENCE(location, commands)]) => analyze(commands, location, stack, config),
+        // a path that must fail leaves the other one to decide
+        (EXEC, [SlotType::EITHER((left_location, left), (right_location, right))]) => {
+            match (analyze(left, left_location, stack.clone(), config), analyze(right, right_location, stack, config)) {
+                (Err(error), Err(_)) => Err(error),
+                (Err(_), Ok(stack)) | (Ok(stack), Err(_)) => Ok(stack),
+                (Ok(Some(left)), Ok(Some(right))) => Ok(left.join(right)),
+                (Ok(_), Ok(_)) => Ok(None),
+            }
+        },
+        (EXEC, [slot]) => { require(Kind::SEQUENCE, slot)?; Ok(None) },
+        (SWAP, [below, above]) => Ok(Some(stack.push(above.to_owned()).push(below.to_owned()))),
+        (POP, [_]) => Ok(Some(stack)),
+        (SEL, [condition, then, otherwise]) => {
+            require(Kind::BOOLEAN, condition)?;
+            Ok(Some(stack.push(then.to_owned().join(otherwise.to_owned()))))
+        },
+        (NGET, [index]) => {
+            require(Kind::INTEGER, index)?;
+            let copy = match index {
+                SlotType::INTEGER(Some(depth)) if *depth >= 1 => match (stack.slots.len().checked_sub(*depth as usize), stack.closed) {
+                    (Some(position), _) => stack.slots[position].to_owned(),
+                    (None, false) => SlotType::INTEGER(None),
+                    (None, true) => return Ok(None),
+                },
+                // out of range is left to the run time, where it can be caught
+                SlotType::INTEGER(Some(_)) => return Ok(None),
+                _ => SlotType::DATA,
+            };
+            match copy {
+                // copying a sequence fails at run time, the same way
+                SlotType::SEQUENCE(..) | SlotType::EITHER(..) => Ok(None),
+                SlotType::UNKNOWN => Ok(Some(stack.push(SlotType::DATA))),
+                copy => Ok(Some(stack.push(copy))),
+            }
+        },
+        (ARITHMETIC(operator), [pre, post]) => {
+            require(Kind::INTEGER, pre)?;
+            require(Kind::INTEGER, post)?;
+            Ok(Some(stack.push(arithmetic_type(operator, pre, post, config))))
+        },
+        (ABS | NEG, [operand]) => {
+            require(Kind::INTEGER, operand)?;
+            Ok(Some(stack.push(match operand {
+                SlotType::INTEGER(_) => SlotType::INTEGER(None),
+                _ => SlotType::DATA,
+            })))
+        },
+        (AT, [list, index]) => { require(Kind::LIST, list)?; require(Kind::INTEGER, index)?; Ok(Some(stack.push(SlotType::UNKNOWN))) },
+        (CONCAT, [pre, post]) => { require(Kind::STRING, pre)?; require(Kind::STRING, post)?; Ok(Some(stack.push(SlotType::DATA))) },
+        (INT | LEN, [text]) => { require(Kind::STRING, text)?; Ok(Some(stack.push(SlotType::INTEGER(None)))) },
+        (STR, [number]) => { require(Kind::INTEGER, number)?; Ok(Some(stack.push(SlotType::DATA))) },
+        (PUSH, [list, _]) => { require(Kind::LIST, list)?; Ok(Some(stack.push(SlotType::DATA))) },
+        (EMIT | PRINT, [value]) => { require(Kind::INTEGER, value)?; Ok(Some(stack)) },
+        (GET, [index]) => { require(Kind::INTEGER, index)?; Ok(Some(stack.push(SlotType::UNKNOWN))) },
+        (PUT, [_, index]) => { require(Kind::INTEGER, index)?; Ok(Some(stack)) },
+        (READ, []) => Ok(Some(stack.push(SlotType::INTEGER(None)))),
+        // whatever follows a throw, a loop or a handler depends on values only known at run time
+        (THROW, [code]) => { require(Kind::INTEGER, code)?; Ok(None) },
+        (REPEAT | ITERATION(_), [count, body]) => { require(Kind::SEQUENCE, body)?; require(Kind::INTEGER, count)?; Ok(None) },
+        (TRY, [body, handler]) => { require(Kind::SEQUENCE, body)?; require(Kind::SEQUENCE, handler)?; Ok(None) },
+        (MAP, [list, body]) => { require(Kind::LIST, list)?; require(Kind::SEQUENCE, body)?; Ok(None) },
+        (FOLD, [list, _, body]) => { require(Kind::LIST, list)?; require(Kind::SEQUENCE, body)?; Ok(None) },
+        (_, _) => Ok(None),
+    }
+}
+
+fn program_stack(program: &PostfixProgram) -> TypeStack {
+    let arguments = program.paramsize as usize + program.variadic as usize;
+    // a variadic program may be given more arguments than it declares
+    TypeStack { slots: vec![SlotType::INTEGER(None); arguments], closed: !program.variadic }
+}
+
+// types of the stack left by a program, from the bottom, None when they depend on values only known at run time
+pub fn infer_types(program: &PostfixProgram, config: &PostfixConfig) -> Result<Option<Vec<PostfixType>>, PostfixError> {
+    let stack = analyze(&program.commands, &[], program_stack(program), config).map_err(|error| match error {
+        PostfixError::MistypedArgument { function, location, expected, actual, .. } =>
+            PostfixError::MistypedArgument { function, position: program.position(&location), location, expected, actual },
+        error => error,
+    })?;
+    Ok(stack.map(|stack| stack.slots.iter().map(SlotType::public).collect()))
+}
+
+// rejects a program with a command that is given a value of the wrong kind however it is run
+pub fn check_types(program: &PostfixProgram, config: &PostfixConfig) -> Result<(), PostfixError> {
+    infer_types(program, config).map(|_| ())
+}